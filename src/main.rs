@@ -1,7 +1,38 @@
 // This lets Rust know the crate exists, and tells it the structure of the files.
 // utils doesn't use the pub keyword because it's not needed for modules.
 mod utils {
+    // Some modules are small libraries with more functions than main.rs uses.
+    // Those functions are marked #[allow(dead_code)] in their own files, so Rust
+    // still warns about anything else that's never used.
     pub mod random_crate;
+    pub mod number_words;
+    pub mod grading;
+    pub mod gradebook;
+    pub mod csv;
+    pub mod json;
+    pub mod string_tools;
+    pub mod text_stats;
+    pub mod case_convert;
+    pub mod template;
+    pub mod fuzzy;
+    pub mod topics;
+    pub mod safe_index;
+    pub mod shopping_list;
+    pub mod history;
+    pub mod fixed_collections;
+    pub mod ordered_map;
+    pub mod grid;
+    pub mod maze;
+    pub mod life;
+    pub mod turtle;
+    pub mod hashing;
+    pub mod auth;
+    pub mod rbac;
+    pub mod sessions;
+    pub mod totp;
+    pub mod people;
+    pub mod validation;
+    pub mod commands;
 }
 
 // This imports the function from the crate.
@@ -9,6 +40,28 @@ mod utils {
 // utils::random_crate::random_integer() every time, but now you can just
 // call random_integer() directly.
 use utils::random_crate::random_integer;
+use utils::number_words::{from_roman, from_words, to_ordinal_words, to_roman, to_words};
+use utils::grading::GradingScale;
+use utils::json::{FromJson, ToJson};
+use utils::string_tools;
+use utils::case_convert::{check_rust_name, NameKind};
+use utils::template::{self, Value};
+use utils::fuzzy::{damerau_levenshtein, get_or_suggest, jaro_winkler, levenshtein};
+// A trait's methods can only be used once the trait is imported.
+use utils::safe_index::SafeIndex;
+use utils::history::{History, MapEdit, VecEdit};
+use utils::fixed_collections::{ArrayVec, Overflow, RingBuffer, SmallVec};
+use utils::ordered_map::{sorted_pairs, MultiMap, OrderedMap};
+use utils::grid::{fit_path, parse_moves, Edges, Grid};
+use utils::maze::{Generator, Maze, Solver};
+use utils::life::{built_in_pattern, Rule, World};
+use utils::turtle;
+use utils::auth::{now_seconds, HashSettings, UserStore};
+use utils::rbac::{AccessPolicy, Subject};
+use utils::sessions::SessionManager;
+use utils::people::{PersonField, PersonRegistry};
+use utils::validation::{self, Validate, Validator};
+use utils::commands::run_command;
 
 // To use HashMap, you must import it from the standard library.
 use std::collections::HashMap;
@@ -28,11 +81,64 @@ fn add1(a: i32, b: i32) -> i32 {
 // If you add a semicolon at the end of the last line, it becomes a statement
 // that returns (), which is the unit type (similar to None in Python).
 
+// Structs
+// Define a structure:
+// #[derive(...)] asks Rust to write some common methods for us:
+// Debug lets you print it with {:?}, Clone gives it .clone(), and PartialEq lets you compare with ==.
+#[derive(Debug, Clone, PartialEq)]
+struct Person {
+    name: String,
+    age: u32,
+}
+
+// Enums
+// Copy means a Direction is copied instead of moved when you assign it or pass it
+// to a function, which is fine for a small value like this.
+// The grid module in utils adds methods to Direction, like turn_left() and opposite().
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    // Diagonals, for moving around a grid:
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+// Enum variants can also hold data. This is useful when each variant needs to store extra information:
+#[derive(Debug, Clone, PartialEq)]
+// The auth module in utils returns these from a real login.
+enum LoginStatus {
+    Success(String),
+    Error(String),
+    // Too many wrong passwords. The number is how many seconds until you can try again.
+    Locked(u64),
+    // The password was right but is too old. Holds the username, so it can be changed.
+    Expired(String),
+    // The password was right, but a one-time code is needed as well. Holds the username.
+    MfaRequired(String),
+}
+
 // The top file is always called main.rs, and the top function is always called main.
 // The main function is always at the bottom of the file.
 
 fn main() {
 
+    // std::env::args() gives you the words typed after the program name.
+    // The first one is the program itself, so we skip it.
+    // If there are any left, e.g. `cargo run -- grade 85`, run that command instead of the lessons.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(e) = run_command(&args) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let sum = add(5, 10);
     println!("The sum is: {}", sum);
 
@@ -68,9 +174,13 @@ fn main() {
 
     // T is a generic type parameter.
     // It can be any type, and Rust will figure it out based on how you use it.
-    fn get_first_element<T>(arr: &[T]) -> &T {
-        &arr[0]
+    // &arr[0] would panic if arr were empty, so this uses first_or from the
+    // SafeIndex trait in utils, which gives back `default` instead.
+    fn get_first_element<'a, T>(arr: &'a [T], default: &'a T) -> &'a T {
+        arr.first_or(default)
     }
+    println!("The first element is: {}", get_first_element(&[7, 8, 9], &0));
+    println!("The first element of an empty list is: {}", get_first_element(&[], &0));
 
     // Constant variables are used to store values that never change.
     // They must be defined with a type, and are usually written in uppercase letters:
@@ -108,6 +218,20 @@ fn main() {
 
     // You can only add a &str to a String with +.
 
+    // The template written inside format! has to be known when the program is compiled.
+    // The template module in utils fills in a template string at run time instead,
+    // using names in the braces and a HashMap of values:
+    let mut values: HashMap<String, Value> = HashMap::new();
+    values.insert(String::from("name"), Value::from(name));
+    values.insert(String::from("topics"), Value::from(vec!["strings", "vectors", "enums"]));
+    let page = "Hello, {name}! Welcome to Rust.\n{#for topic in topics}- {topic:>8}\n{/for}";
+    match template::render(page, &values) {
+        Ok(text) => print!("{}", text),
+        Err(e) => println!("Template error: {}", e),
+    }
+    // A missing value is an error rather than a panic:
+    println!("{:?}", template::render("Goodbye, {surname}!", &values).map_err(|e| e.to_string()));
+
     // The format! macro is very flexible and can take multiple arguments.
     let x = 5;
     let y = 10;
@@ -145,6 +269,20 @@ fn main() {
     let modified_greeting: String = chars.into_iter().collect();
     println!("Modified greeting: {}", modified_greeting);
 
+    // Both of those panic if the index is too big. The string_tools module in utils
+    // has versions that return an Option or Result instead:
+    let mut word = String::from("héllo");
+    println!("{} has {} bytes but {} chars", word, word.len(), string_tools::char_len(&word));
+    println!("Char 1 is {:?}, char 10 is {:?}", string_tools::char_at(&word, 1), string_tools::char_at(&word, 10));
+    println!("Replacing char 0: {:?}, now {}", string_tools::replace_char(&mut word, 0, 'j'), word);
+    println!("Replacing char 10: {:?}", string_tools::replace_char(&mut word, 10, 'j'));
+    println!("Chars 1 to 3 are {:?}", string_tools::char_slice(&word, 1, 3));
+    // Some characters on screen are made of several chars, like this family emoji (5 chars).
+    // A "grapheme" is one character as a person would see it:
+    let family = "hi 👨‍👩‍👧!";
+    println!("{} has {} chars but {} graphemes", family, family.chars().count(), string_tools::grapheme_len(family));
+    println!("Grapheme 3 is {:?}", string_tools::grapheme_at(family, 3));
+
     // Arrays
     // The size of an array is fixed. You cannot add or remove elements.
     // All the values in an array must be of the same type.
@@ -155,6 +293,29 @@ fn main() {
 
     // When printing the whole array, you must use {:?} inside println!:
     println!("The fruits array is: {:?}", fruits);
+
+    // The fixed_collections module in utils has types in between an array and a Vec.
+    // An ArrayVec can hold up to 3 fruits, and push() gives an error when it's full:
+    let mut basket: ArrayVec<&str, 3> = ArrayVec::new();
+    for fruit in ["Apple", "Banana", "Cherry", "Date"] {
+        if let Err(e) = basket.push(fruit) {
+            println!("Basket: {}", e);
+        }
+    }
+    println!("The basket is: {:?}", basket);
+    // A RingBuffer keeps the newest 3 and drops the oldest:
+    let mut recent: RingBuffer<&str, 3> = RingBuffer::new(Overflow::Overwrite);
+    for fruit in ["Apple", "Banana", "Cherry", "Date"] {
+        if let Ok(Some(dropped)) = recent.push(fruit) {
+            println!("The ring buffer dropped {}", dropped);
+        }
+    }
+    println!("The 3 most recent fruits are: {:?}", recent);
+    // A SmallVec keeps up to 3 items in an array, then moves them into a Vec:
+    let mut small: SmallVec<&str, 3> = fruits.iter().copied().collect();
+    println!("{:?} spilled to a Vec: {}", small, small.is_spilled());
+    small.push("Date");
+    println!("{:?} spilled to a Vec: {}", small, small.is_spilled());
     
     // Vectors
     // Vectors are similar to arrays, but they can grow and shrink in size.
@@ -201,6 +362,36 @@ fn main() {
     // When you loop through a vector without &, the values are moved out, and you can no longer
     // use the vector. But when you borrow the vector using &, you can still use it later in your program.
 
+    // Once veggies is cleared, the old values are gone. The history module in utils
+    // records each change so it can be undone and redone:
+    let mut veggie_history = History::new(vec!["Carrot", "Potato", "Cucumber"]);
+    let edits = [VecEdit::Push("Tomato"), VecEdit::Remove(0), VecEdit::Set(0, "Spinach"), VecEdit::Clear];
+    for edit in edits {
+        if let Err(e) = veggie_history.apply(edit) {
+            println!("Couldn't apply the change: {}", e);
+        }
+    }
+    println!("After the changes: {:?}", veggie_history.value());
+    // while let keeps looping for as long as the pattern matches, so this undoes
+    // everything and stops at the first Err ("There is nothing to undo").
+    while let Ok(change) = veggie_history.undo() {
+        println!("  {}", change);
+    }
+    println!("After undoing everything: {:?}", veggie_history.value());
+    println!("Can undo: {}, can redo: {}", veggie_history.can_undo(), veggie_history.can_redo());
+    if let Ok(change) = veggie_history.redo() {
+        println!("  {}", change);
+    }
+    // A new change after an undo drops the steps that could have been redone.
+    if let Err(e) = veggie_history.apply(VecEdit::Pop) {
+        println!("Couldn't apply the change: {}", e);
+    }
+    println!("After one redo and a pop: {:?}", veggie_history.value());
+    println!("The full log:");
+    for line in veggie_history.log() {
+        println!("  {}", line);
+    }
+
     // Vectors and arrays both allow indexing to access elements.
     let mut numbers: Vec<i32> = vec![1, 2, 3, 4, 5];
     println!("The first number is: {}", numbers[0]);
@@ -266,6 +457,19 @@ fn main() {
         println!("Bob's score not found.");
     }
 
+    // If you mistype a key, get() just gives you None. The fuzzy module in utils
+    // can suggest the key you probably meant:
+    match get_or_suggest(&scores, "Alcie") {
+        Ok(score) => println!("Alcie's score: {}", score),
+        Err(e) => println!("{}", e),
+    }
+    println!(
+        "Alcie vs Alice: Levenshtein {}, Damerau-Levenshtein {}, Jaro-Winkler {:.3}",
+        levenshtein("Alcie", "Alice"),
+        damerau_levenshtein("Alcie", "Alice"),
+        jaro_winkler("Alcie", "Alice")
+    );
+
     // You can use indexing syntax to access values by key:
     println!("Alice's score (via indexing): {}", scores["Alice"]);
     // If you insert a new value using a key that already exists, the old value is replaced with the new one:
@@ -273,9 +477,25 @@ fn main() {
     println!("Alice's score after reinserting: {}", scores["Alice"]);
     // This is how you print out the whole HashMap, with {:?}:
     println!("Scores HashMap: {:?}", scores);
+    // History from the vectors section works with a HashMap too. with_max_depth
+    // limits how many changes can be undone:
+    let mut score_history = History::with_max_depth(scores.clone(), 10);
+    for edit in [MapEdit::Insert("Bob", 70), MapEdit::Insert("Alice", 50), MapEdit::Remove("Carol")] {
+        if let Err(e) = score_history.apply(edit) {
+            println!("Couldn't apply the change: {}", e);
+        }
+    }
+    if let Ok(change) = score_history.undo() {
+        println!("{}, leaving {:?}", change, score_history.value());
+    }
 
     // You can also use a for loop to loop through all the key/value pairs in a hashmap:
+    // (Rust will warn about the name capitalCities. Variable names should be snake_case,
+    // and the case_convert module in utils can tell you the fix, printed below.)
     let mut capitalCities = HashMap::new();
+    if let Some(advice) = check_rust_name("capitalCities", NameKind::Variable) {
+        println!("{}", advice);
+    }
 
     // Add keys and values (Country, City)
     capitalCities.insert("England", "London");
@@ -287,12 +507,40 @@ fn main() {
     println!("The capital of {} is {}.", country, city);
     }
 
-    // Structs
-    // Define a structure:
-    struct Person {
-        name: String,
-        age: u32,
+    // The loop above prints the countries in a different order every time the
+    // program runs, because a HashMap doesn't keep any order. sorted_pairs from the
+    // ordered_map module in utils sorts them by key first:
+    for (country, city) in sorted_pairs(&capitalCities) {
+        println!("Sorted: the capital of {} is {}.", country, city);
     }
+    // An OrderedMap remembers the order the keys were added in:
+    let mut ordered_scores = OrderedMap::new();
+    ordered_scores.insert("Bob", 85);
+    ordered_scores.insert("Alice", 90);
+    *ordered_scores.entry("Carol").or_insert(0) += 70;
+    ordered_scores.entry("Bob").and_modify(|score| *score += 5);
+    println!("Ordered scores: {:?}, Alice has {}", ordered_scores, ordered_scores["Alice"]);
+    // A MultiMap lets one key have several values, like a country with several cities:
+    let mut cities = MultiMap::new();
+    cities.insert("England", "London");
+    cities.insert("Norway", "Oslo");
+    cities.insert("England", "Manchester");
+    println!("Cities in England: {:?}", cities.get("England"));
+    cities.remove_value("Norway", &"Oslo");
+    println!("All cities: {:?}", cities);
+
+    // The json module in utils can write a HashMap as JSON, and read it back:
+    let json_text = capitalCities.to_json().to_string();
+    println!("As JSON: {}", json_text);
+    let from_json: Result<HashMap<String, String>, String> = utils::json::parse(&json_text)
+        .map_err(|e| e.to_string())
+        .and_then(|value| HashMap::from_json(&value));
+    println!("Read back from JSON: {:?}", from_json);
+    println!("Scores as JSON: {}", scores.to_json());
+
+    // Structs
+    // The Person structure is defined near the top of this file, outside main(),
+    // so that the modules in utils can use it too (they call it crate::Person).
 
     // create an object (an instance that has the same structure)
     // Use mut if you want to be able to change the values later.
@@ -305,14 +553,52 @@ fn main() {
     println!("Name: {}", user.name);
     println!("Updated age: {}", user.age);
 
-    // Enums
-    enum Direction {
-        Up,
-        Down,
-        Left,
-        Right,
+    // The people module in utils keeps lots of people in a PersonRegistry.
+    // Each one gets an id, and bad data (an empty name, an age of 300) is refused.
+    let mut registry = PersonRegistry::new();
+    for (name, age) in [("John", 36), ("alice", 29), ("Bob", 41), ("", 20), ("Old Tom", 300)] {
+        if let Err(e) = registry.add(name, age) {
+            println!("Couldn't add '{}': {}", name, e);
+        }
+    }
+    if let Some(found) = registry.find_by_name("ALICE").first() {
+        println!("Found alice with id {}", found.id);
+    }
+    for record in registry.sorted(PersonField::Age, true) {
+        println!("#{} {} ({})", record.id, record.person.name, record.person.age);
+    }
+    println!("People aged 30 to 40: {}", registry.age_range(30..=40).len());
+    println!("{} people, and {} with a name starting with B", registry.len(), registry.filter(|p| p.name.starts_with('B')).len());
+    if let Some(person) = registry.get(1) {
+        println!("Person #1 is {}", person.name);
+    }
+
+    // Making a Person with { } by hand skips all those checks. validate(), from the
+    // validation module, checks it afterwards and lists every problem, not just the first.
+    let unchecked = Person {
+        name: String::from("   "),
+        age: 300,
+    };
+    match unchecked.validate() {
+        Ok(()) => println!("{} looks fine", unchecked.name),
+        Err(violations) => {
+            for violation in violations {
+                println!("Problem with {}", violation);
+            }
+        }
+    }
+    // A Validator can check any values, not just a struct's fields. pattern() is
+    // like a small regular expression: \d is a digit and {3} means 3 of them.
+    let mut check = Validator::new();
+    check.text("phone", "55-12345").pattern(r"\d{3}-\d{4}");
+    check.number("rating", 7).range(1..=5);
+    if let Err(violations) = check.finish() {
+        println!("Problems: {}", validation::describe(&violations));
     }
 
+    // Enums
+    // Direction is defined near the top of this file, next to Person.
+
     let my_direction = Direction::Up;
 
     match my_direction {
@@ -320,21 +606,135 @@ fn main() {
         Direction::Down => println!("We are going down!"),
         Direction::Left => println!("We are going left!"),
         Direction::Right => println!("We are going right!"),
+        // A match must cover every variant. This arm covers all four diagonals at once:
+        Direction::UpLeft | Direction::UpRight | Direction::DownLeft | Direction::DownRight => {
+            println!("We are going diagonally!")
+        }
     }
 
-    // Enum variants can also hold data. This is useful when each variant needs to store extra information:
-    enum LoginStatus {
-        Success(String),
-        Error(String),
+    // The grid module in utils gives Direction some methods, and can follow a
+    // list of moves around a Grid:
+    println!(
+        "Turning right from {:?} faces {:?}; turning left faces {:?}; the opposite is {:?}",
+        my_direction,
+        my_direction.turn_right(),
+        my_direction.turn_left(),
+        my_direction.opposite()
+    );
+    match parse_moves("UUR (DR) 2D") {
+        Ok(moves) => {
+            let (width, height, start) = fit_path(&moves);
+            if let Ok(mut grid) = Grid::new(width, height, '.', Edges::Bounded) {
+                if let Ok(path) = grid.walk(start, &moves) {
+                    grid.draw_path(&path, &moves);
+                    println!("UUR (DR) 2D goes from {} to {}:", start, path[path.len() - 1]);
+                }
+                print!("{}", grid);
+            }
+        }
+        Err(e) => println!("Error: {}", e),
     }
+    // The maze module uses the grid to make random mazes, and finds a way
+    // through as a Vec<Direction>. Try `cargo run -- maze --solve all` too.
+    if let Ok(maze) = Maze::generate(Generator::Backtracker, 5, 3, &mut rand::rng())
+        && let Some(solution) = maze.solve(maze.entrance(), maze.exit(), Solver::AStar)
+    {
+        println!("A maze solved in {} moves: {:?}", solution.route.len(), solution.route);
+        print!("{}", maze.render(Some(&solution.route)));
+    }
+    // The turtle module is a small drawing language. The turtle starts facing
+    // Direction::Up and draws a line wherever it moves. `cargo run -- turtle <file>`
+    // runs a program from a file and saves the drawing as a picture.
+    let flower = "to square :size\n  repeat 4 [ fd :size rt 90 ]\nend\nrepeat 6 [ square 40 rt 60 ]";
+    match turtle::draw(flower) {
+        Ok(drawing) => println!("The turtle drew {} lines, and the SVG is {} bytes long", drawing.lines.len(), drawing.to_svg().len()),
+        Err(e) => println!("Turtle error: {}", e),
+    }
+
+    // Enum variants can also hold data. LoginStatus (also near the top of this file) is an example.
 
     let result1 = LoginStatus::Success(String::from("Welcome, John!"));
     let result2 = LoginStatus::Error(String::from("Incorrect password"));
 
+    // The json module in utils can turn these types into JSON text and back again:
+    println!("{}", user.to_json());
+    println!("{}", result2.to_json().pretty());
+    match Direction::from_json(&my_direction.to_json()) {
+        Ok(direction) => println!("Read back from JSON: {:?}", direction),
+        Err(e) => println!("Error: {}", e),
+    }
+
     match result1 {
         LoginStatus::Success(message) => println!("Success: {}", message),
         LoginStatus::Error(message) => println!("Error: {}", message),
+        LoginStatus::Locked(seconds) => println!("Locked: try again in {} seconds", seconds),
+        LoginStatus::Expired(username) => println!("{}'s password has expired", username),
+        LoginStatus::MfaRequired(username) => println!("{} needs to enter a one-time code", username),
+    }
+
+    // The auth module in utils gives back a LoginStatus from a real username and
+    // password. Passwords are stored as slow, salted hashes; the small settings
+    // here just make the lesson run quickly. Three wrong passwords lock the account.
+    let mut accounts = UserStore::with_settings(HashSettings { space: 64, rounds: 1 });
+    match accounts.add_user("john", "correct horse") {
+        Ok(()) => {
+            for attempt in ["correct horse", "wrong", "wrong", "wrong", "correct horse"] {
+                println!("Logging in with '{}': {:?}", attempt, accounts.login("john", attempt));
+            }
+            if let Some(user) = accounts.user("JOHN") {
+                println!("{} has given {} wrong password(s) in a row", user.username, user.failed_attempts);
+            }
+        }
+        Err(e) => println!("Error: {}", e),
+    }
+
+    // With one-time codes turned on, the right password only gets as far as
+    // MfaRequired. The totp module works out the same 6-digit code an
+    // authenticator app would show, and login_with_code finishes logging in.
+    match accounts.add_user("amy", "open sesame").and_then(|()| accounts.enable_mfa("amy")) {
+        Ok(totp) => {
+            println!("Add this to an authenticator app: {}", totp.uri("Rust Lessons", "amy"));
+            if let LoginStatus::MfaRequired(username) = accounts.login("amy", "open sesame") {
+                let code = totp.code_at(now_seconds());
+                println!("{} typed the code {}: {:?}", username, code, accounts.login_with_code(&username, &code));
+            }
+        }
+        Err(e) => println!("Error: {}", e),
+    }
+
+    // After a Success, the sessions module hands out a token to use instead of
+    // the password. It's signed, so a changed token is spotted, and it stops
+    // working when it expires, isn't used for a while, or is revoked (logging out).
+    let mut sessions = SessionManager::new();
+    let token = sessions.start("john");
+    match sessions.verify(&token) {
+        Ok(session) => println!("The token belongs to {}", session.username),
+        Err(e) => println!("Error: {}", e),
+    }
+    let tampered = token.replacen(|c: char| c.is_ascii_digit(), "x", 1);
+    if let Err(e) = sessions.verify(&tampered) {
+        println!("Tampered token: {}", e);
     }
+    if sessions.revoke(&token).is_ok()
+        && let Err(e) = sessions.verify(&token)
+    {
+        println!("After logging out: {}", e);
+    }
+    // Refreshing swaps a token for a new one, and the old one stops working.
+    let old_token = sessions.start("john");
+    if let Ok(new_token) = sessions.refresh(&old_token)
+        && let Err(e) = sessions.verify(&old_token)
+    {
+        println!("Refreshed to {}... and the old token gives: {}", &new_token[..8], e);
+    }
+    // "Log out everywhere" ignores upper and lower case, like usernames do.
+    sessions.start("John");
+    println!("Logged john out of {} session(s)", sessions.revoke_user("JOHN"));
+    println!(
+        "Cleared away {} ended session(s); {} still running",
+        sessions.remove_expired(),
+        sessions.sessions().count()
+    );
 
     // Operators
     let sum = 5 + 10;           // Addition
@@ -353,6 +753,26 @@ fn main() {
     println!("Has any access: {}", logged_in || is_admin); // True if at least one is true
     println!("Not logged in: {}", !logged_in);
 
+    // The rbac module in utils turns checks like these into rules that can be
+    // read from a file. Roles give permissions, and a role can inherit another's.
+    // The first rule that matches decides, and the Decision says which one it was.
+    let rules = "role user: posts:read\n\
+                 role admin inherits user: *\n\
+                 deny * if not logged_in\n\
+                 allow posts:write if logged_in && !is_admin && is_owner";
+    match AccessPolicy::parse(rules) {
+        Ok(policy) => {
+            let mut visitor = Subject::new(&["user"]);
+            visitor.set("logged_in", logged_in);
+            visitor.set("is_admin", is_admin);
+            visitor.set("is_owner", false);
+            for action in ["posts:read", "posts:write", "posts:delete"] {
+                println!("{} => {}", action, policy.check(&visitor, action));
+            }
+        }
+        Err(e) => println!("Policy error: {}", e),
+    }
+
     // Control Flow
     let score = 85;
 
@@ -366,6 +786,13 @@ fn main() {
         println!("Grade: F");
     }
 
+    // The grading module in utils does the same thing, but the grades live in a
+    // GradingScale that can be loaded from a file (try `cargo run -- grade 85`):
+    match GradingScale::default().grade(score as f64) {
+        Ok(grade) => println!("Grade from the default scale: {}", grade),
+        Err(e) => println!("Error: {}", e),
+    }
+
     // You can assign the result of an if to a variable
     // BUT they must return the same type!
     let time = 20;
@@ -425,12 +852,20 @@ fn main() {
     let number = 4;
 
     let description = match number {
-        1 => "One",
-        2 => "Two",
-        3 => "Three",
-        4 => "Four",
-        _ => "Unknown",
+        1 => String::from("One"),
+        2 => String::from("Two"),
+        3 => String::from("Three"),
+        4 => String::from("Four"),
+        // Instead of giving up with "Unknown", spell out any other number.
+        _ => to_words(number),
     };
+    println!("{} is {}", number, description);
+
+    // The number_words module in utils can spell any i64, and convert to and from Roman numerals:
+    println!("42 is {} and the 42nd is the {}", to_words(42), to_ordinal_words(42));
+    println!("\"forty-two\" as a number is {:?}", from_words("forty-two"));
+    println!("1994 in Roman numerals is {:?}", to_roman(1994));
+    println!("MCMXCIV is {:?}, but IIII is {:?}", from_roman("MCMXCIV"), from_roman("IIII"));
 
     // Rust has three types of loops: loop, while, and for.
 
@@ -521,6 +956,17 @@ fn main() {
     // the iter() method returns an iterator over the array,
     // and enumerate() adds a counter to it, giving us both the index and the value.
 
+    // The life module in utils runs Conway's Game of Life, which is just loops over
+    // a grid of cells. A blinker flips between a row and a column of 3 cells:
+    if let Some(blinker) = built_in_pattern("blinker")
+        && let Ok(mut world) = World::around(&blinker, 1, Rule::conway(), Edges::Bounded)
+    {
+        for _ in 0..3 {
+            println!("Generation {}:\n{}", world.generation(), world);
+            world.step();
+        }
+    }
+
     // Each value has one owner, usually a variable.
     // When the owner goes out of scope, the value is deleted.
     // This is called ownership, and it helps manage memory safely without a
@@ -612,12 +1058,12 @@ fn main() {
     }
 
     // Here's an example of using Option:
-    fn get_nth_element(vec: &Vec<i32>, n: usize) -> Option<i32> {
-        if n < vec.len() {
-            Some(vec[n])
-        } else {
-            None
-        }
+    // Taking &[T] instead of &Vec<i32> means it works for arrays and Vecs of any type,
+    // and returning &T borrows the value instead of copying it.
+    // nth_checked comes from the SafeIndex trait in utils and returns a Result;
+    // .ok() turns that into an Option, throwing the error away.
+    fn get_nth_element<T>(slice: &[T], n: usize) -> Option<&T> {
+        slice.nth_checked(n).ok()
     }
 
     let numbers = vec![10, 20, 30];
@@ -626,4 +1072,11 @@ fn main() {
         None => println!("No value found at that index"),
     }
 
+    // SafeIndex has a few other ways of indexing that never panic:
+    println!("numbers.at(-1) = {:?}", numbers.at(-1)); // Python-style, counting from the end
+    println!("numbers.at(5) = {:?}", numbers.at(5).map_err(|e| e.to_string()));
+    println!("numbers.wrapping_at(4) = {:?}", numbers.wrapping_at(4)); // goes round again
+    println!("numbers.clamped_at(99) = {:?}", numbers.clamped_at(99)); // stops at the end
+    println!("numbers.sub_slice(1, 3) = {:?}", numbers.sub_slice(1, 3));
+
 }
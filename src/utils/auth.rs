@@ -0,0 +1,676 @@
+// Logging in with a username and password, giving back a LoginStatus.
+//
+// Passwords are never stored. Instead each user has a password hash: the
+// password mixed with a random "salt" and run through a slow hash function.
+// Logging in hashes the typed password the same way and compares the results.
+// The salt means two users with the same password get different hashes, so
+// an attacker who steals the file has to crack each one separately.
+//
+// The hash is "balloon hashing" (Boneh, Corrigan-Gibbs and Schechter, 2016),
+// built on the SHA-256 in hashing.rs. It fills a buffer of blocks and then
+// mixes them round and round, jumping to blocks picked by the salt. That makes
+// it "memory-hard": every guess needs the whole buffer, so an attacker can't
+// just run millions of guesses at once on a graphics card.
+//
+// Other protections:
+// - After a few wrong passwords the account is locked for a while, and each
+//   wrong password after that doubles the wait ("backoff").
+// - Passwords can have a maximum age, after which login returns Expired.
+// - A user can need a one-time code as well: login returns MfaRequired, and
+//   login_with_code checks the code from their authenticator app (see totp.rs).
+// - The store is saved by writing a temporary file and renaming it over the
+//   old one, so a crash halfway through a save never leaves half a file.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+
+use crate::utils::hashing::{self, Sha256};
+use crate::utils::json::{self, FromJson, JsonValue, ToJson};
+use crate::utils::totp::Totp;
+use crate::LoginStatus;
+
+// The same message for an unknown user and a wrong password, so nobody can
+// find out which usernames exist by trying them.
+const WRONG_DETAILS: &str = "Wrong username or password";
+
+const SALT_LENGTH: usize = 16;
+
+// How long after the right password a one-time code can be given, in seconds.
+const CODE_TIMEOUT: u64 = 5 * 60;
+
+// How many times each block is mixed with other blocks in a round.
+const MIXES_PER_BLOCK: u64 = 3;
+
+// Limits on the settings read from a file, so a broken file can't ask for
+// gigabytes of memory or hours of hashing.
+const MAX_SPACE: usize = 1 << 20;
+const MAX_ROUNDS: u32 = 64;
+
+// Seconds since 1 January 1970 (Unix time).
+pub fn now_seconds() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// How much work a password hash takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HashSettings {
+    // How many 32-byte blocks the buffer has. 4096 blocks is 128 KiB.
+    pub space: usize,
+    // How many times the whole buffer is mixed.
+    pub rounds: u32,
+}
+
+impl Default for HashSettings {
+    fn default() -> Self {
+        HashSettings { space: 4096, rounds: 3 }
+    }
+}
+
+impl HashSettings {
+    fn check(&self) -> Result<(), String> {
+        if self.space == 0 || self.space > MAX_SPACE {
+            return Err(format!("The hash space must be from 1 to {} blocks, not {}", MAX_SPACE, self.space));
+        }
+        if self.rounds == 0 || self.rounds > MAX_ROUNDS {
+            return Err(format!("The hash rounds must be from 1 to {}, not {}", MAX_ROUNDS, self.rounds));
+        }
+        Ok(())
+    }
+}
+
+// Balloon hashing. The three steps are from the paper: expand, mix, extract.
+fn balloon(password: &[u8], salt: &[u8], settings: HashSettings) -> [u8; 32] {
+    let space = settings.space;
+    // Every hash includes a counter that goes up each time, so no two of
+    // them are ever hashing exactly the same thing.
+    let mut counter: u64 = 0;
+    let mut hash = |parts: &[&[u8]]| -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(&counter.to_le_bytes());
+        counter += 1;
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finish()
+    };
+
+    // 1. Expand: fill the buffer, each block the hash of the one before.
+    let mut blocks = vec![[0u8; 32]; space];
+    blocks[0] = hash(&[password, salt]);
+    for m in 1..space {
+        let previous = blocks[m - 1];
+        blocks[m] = hash(&[&previous]);
+    }
+
+    // 2. Mix: hash each block with the one before it and with a few others.
+    // Which others depends on the salt, so it can't be worked out in advance.
+    for round in 0..settings.rounds as u64 {
+        for m in 0..space {
+            let previous = blocks[(m + space - 1) % space];
+            blocks[m] = hash(&[&previous, &blocks[m]]);
+            for i in 0..MIXES_PER_BLOCK {
+                let position = [round.to_le_bytes(), (m as u64).to_le_bytes(), i.to_le_bytes()].concat();
+                let pick = hash(&[salt, &position]);
+                let mut first_bytes = [0; 8];
+                first_bytes.copy_from_slice(&pick[..8]);
+                let other = blocks[(u64::from_le_bytes(first_bytes) % space as u64) as usize];
+                blocks[m] = hash(&[&blocks[m], &other]);
+            }
+        }
+    }
+
+    // 3. Extract: the last block is the result.
+    blocks[space - 1]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordHash {
+    settings: HashSettings,
+    salt: Vec<u8>,
+    hash: [u8; 32],
+}
+
+impl PasswordHash {
+    // Hashes a password with a new random salt.
+    pub fn new(password: &str, settings: HashSettings) -> PasswordHash {
+        let mut salt = [0u8; SALT_LENGTH];
+        rand::rng().fill(&mut salt);
+        PasswordHash::with_salt(password, &salt, settings)
+    }
+
+    pub fn with_salt(password: &str, salt: &[u8], settings: HashSettings) -> PasswordHash {
+        PasswordHash {
+            settings,
+            salt: salt.to_vec(),
+            hash: balloon(password.as_bytes(), salt, settings),
+        }
+    }
+
+    pub fn settings(&self) -> HashSettings {
+        self.settings
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        let attempt = balloon(password.as_bytes(), &self.salt, self.settings);
+        hashing::constant_time_eq(&attempt, &self.hash)
+    }
+
+    // Reads the text written by Display:
+    // "balloon$<space>$<rounds>$<salt in hex>$<hash in hex>".
+    pub fn parse(text: &str) -> Result<PasswordHash, String> {
+        let parts: Vec<&str> = text.split('$').collect();
+        let ["balloon", space, rounds, salt, hash] = parts.as_slice() else {
+            return Err(String::from("A password hash should look like balloon$<space>$<rounds>$<salt>$<hash>"));
+        };
+        let settings = HashSettings {
+            space: space.parse().map_err(|_| format!("The hash space '{}' isn't a whole number", space))?,
+            rounds: rounds.parse().map_err(|_| format!("The hash rounds '{}' isn't a whole number", rounds))?,
+        };
+        settings.check()?;
+        let hash: [u8; 32] = hashing::from_hex(hash)?
+            .try_into()
+            .map_err(|_| String::from("The hash part of a password hash must be 32 bytes"))?;
+        Ok(PasswordHash {
+            settings,
+            salt: hashing::from_hex(salt)?,
+            hash,
+        })
+    }
+}
+
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "balloon${}${}${}${}",
+            self.settings.space,
+            self.settings.rounds,
+            hashing::to_hex(&self.salt),
+            hashing::to_hex(&self.hash)
+        )
+    }
+}
+
+// The rules for passwords and lockouts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    pub min_password_length: usize,
+    // Wrong passwords in a row before the account is locked.
+    pub attempts_before_lock: u32,
+    // How long the first lock lasts, in seconds. Each wrong password after
+    // that doubles it, up to longest_lock.
+    pub first_lock: u64,
+    pub longest_lock: u64,
+    // How many seconds a password lasts before it has to be changed. None means forever.
+    pub max_password_age: Option<u64>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            min_password_length: 8,
+            attempts_before_lock: 3,
+            first_lock: 30,
+            longest_lock: 60 * 60,
+            max_password_age: None,
+        }
+    }
+}
+
+impl Policy {
+    // How long to lock an account after this many wrong passwords in a row.
+    // With the defaults: nothing for 1 or 2, then 30s, 60s, 120s, ... up to an hour.
+    pub fn lock_seconds(&self, failed_attempts: u32) -> Option<u64> {
+        let extra = failed_attempts.checked_sub(self.attempts_before_lock)?;
+        // Doubling is a shift left by one bit. checked_shl and saturating_mul stop it overflowing.
+        let doubled = 1u64.checked_shl(extra).map_or(u64::MAX, |times| self.first_lock.saturating_mul(times));
+        Some(doubled.min(self.longest_lock))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub username: String,
+    password: PasswordHash,
+    // When the password was set, in Unix time.
+    pub password_changed: u64,
+    // Wrong passwords in a row. A right one sets it back to 0.
+    pub failed_attempts: u32,
+    // The Unix time the lock ends. Any time in the past means it isn't locked.
+    pub locked_until: u64,
+    // A base32 secret shared with the user's authenticator app. If there is
+    // one, a right password gives MfaRequired, and login_with_code finishes.
+    pub mfa_secret: Option<String>,
+    // The time step of the last one-time code used, so it can't be used again.
+    pub last_code_step: Option<u64>,
+}
+
+pub struct UserStore {
+    users: Vec<User>,
+    // Used for new passwords. Older hashes are upgraded when their user logs in.
+    pub settings: HashSettings,
+    pub policy: Policy,
+    // Users who gave the right password and now need to give a one-time code,
+    // and when that chance runs out. The keys are lowercase usernames.
+    waiting_for_code: HashMap<String, u64>,
+    // Where the time comes from. Tests can swap in a fake clock to skip ahead.
+    clock: fn() -> u64,
+}
+
+impl Default for UserStore {
+    fn default() -> Self {
+        UserStore::new()
+    }
+}
+
+impl UserStore {
+    pub fn new() -> UserStore {
+        UserStore::with_settings(HashSettings::default())
+    }
+
+    pub fn with_settings(settings: HashSettings) -> UserStore {
+        UserStore {
+            users: Vec::new(),
+            settings,
+            policy: Policy::default(),
+            waiting_for_code: HashMap::new(),
+            clock: now_seconds,
+        }
+    }
+
+    // Only the tests below use this for now.
+    #[allow(dead_code)]
+    pub fn set_clock(&mut self, clock: fn() -> u64) {
+        self.clock = clock;
+    }
+
+    pub fn users(&self) -> &[User] {
+        &self.users
+    }
+
+    // Usernames are case-insensitive: "John" and "john" are the same user.
+    fn position(&self, username: &str) -> Option<usize> {
+        self.users.iter().position(|u| u.username.eq_ignore_ascii_case(username))
+    }
+
+    pub fn user(&self, username: &str) -> Option<&User> {
+        self.position(username).map(|i| &self.users[i])
+    }
+
+    fn user_mut(&mut self, username: &str) -> Result<&mut User, String> {
+        match self.position(username) {
+            Some(i) => Ok(&mut self.users[i]),
+            None => Err(format!("There is no user called {}", username)),
+        }
+    }
+
+    fn check_password(&self, username: &str, password: &str) -> Result<(), String> {
+        let length = password.chars().count();
+        if length < self.policy.min_password_length {
+            return Err(format!("The password must be at least {} characters long", self.policy.min_password_length));
+        }
+        if length > 1024 {
+            return Err(String::from("The password can't be more than 1024 characters long"));
+        }
+        if password.eq_ignore_ascii_case(username) {
+            return Err(String::from("The password can't be the same as the username"));
+        }
+        Ok(())
+    }
+
+    pub fn add_user(&mut self, username: &str, password: &str) -> Result<(), String> {
+        check_username(username)?;
+        if self.position(username).is_some() {
+            return Err(format!("There is already a user called {}", username));
+        }
+        self.check_password(username, password)?;
+        self.users.push(User {
+            username: username.to_string(),
+            password: PasswordHash::new(password, self.settings),
+            password_changed: (self.clock)(),
+            failed_attempts: 0,
+            locked_until: 0,
+            mfa_secret: None,
+            last_code_step: None,
+        });
+        Ok(())
+    }
+
+    pub fn remove_user(&mut self, username: &str) -> Result<(), String> {
+        let index = self
+            .position(username)
+            .ok_or_else(|| format!("There is no user called {}", username))?;
+        self.users.remove(index);
+        Ok(())
+    }
+
+    // Changes a password, which also fixes an expired one. The old password
+    // is checked like a login, so wrong guesses here count towards a lock too.
+    pub fn change_password(&mut self, username: &str, old_password: &str, new_password: &str) -> Result<(), String> {
+        match self.login(username, old_password) {
+            LoginStatus::Success(_) | LoginStatus::Expired(_) => {}
+            LoginStatus::MfaRequired(_) => {
+                return Err(String::from("This account needs a one-time code, so its password can't be changed here"));
+            }
+            LoginStatus::Locked(seconds) => return Err(format!("The account is locked for {} more second(s)", seconds)),
+            LoginStatus::Error(message) => return Err(message),
+        }
+        if old_password == new_password {
+            return Err(String::from("The new password must be different from the old one"));
+        }
+        self.check_password(username, new_password)?;
+        let (settings, now) = (self.settings, (self.clock)());
+        let user = self.user_mut(username)?;
+        user.password = PasswordHash::new(new_password, settings);
+        user.password_changed = now;
+        Ok(())
+    }
+
+    pub fn set_mfa_secret(&mut self, username: &str, secret: Option<String>) -> Result<(), String> {
+        let user = self.user_mut(username)?;
+        user.mfa_secret = secret;
+        user.last_code_step = None;
+        Ok(())
+    }
+
+    // Turns on one-time codes with a new secret, and returns it so it can be
+    // shown to the user (usually as an otpauth:// link).
+    pub fn enable_mfa(&mut self, username: &str) -> Result<Totp, String> {
+        let totp = Totp::random();
+        self.set_mfa_secret(username, Some(totp.secret_base32()))?;
+        Ok(totp)
+    }
+
+    // Lets a locked user try again straight away.
+    pub fn unlock(&mut self, username: &str) -> Result<(), String> {
+        let user = self.user_mut(username)?;
+        user.failed_attempts = 0;
+        user.locked_until = 0;
+        Ok(())
+    }
+
+    pub fn login(&mut self, username: &str, password: &str) -> LoginStatus {
+        let now = (self.clock)();
+        let Some(index) = self.position(username) else {
+            // Hash the password anyway, so an unknown username takes as long
+            // as a wrong password and the timing doesn't give it away.
+            PasswordHash::with_salt(password, &[0; SALT_LENGTH], self.settings);
+            return LoginStatus::Error(String::from(WRONG_DETAILS));
+        };
+
+        // While locked, the password isn't even checked.
+        let user = &mut self.users[index];
+        if user.locked_until > now {
+            return LoginStatus::Locked(user.locked_until - now);
+        }
+        if !user.password.verify(password) {
+            return self.wrong_attempt(index, now, WRONG_DETAILS);
+        }
+        // This is the only time the password is known, so it's the chance to
+        // upgrade a hash made with older settings.
+        if user.password.settings() != self.settings {
+            user.password = PasswordHash::new(password, self.settings);
+        }
+
+        // The failed attempts aren't reset until the code is right too.
+        // Otherwise someone who knew the password could keep guessing codes.
+        if user.mfa_secret.is_some() {
+            let username = user.username.clone();
+            self.waiting_for_code.insert(username.to_lowercase(), now.saturating_add(CODE_TIMEOUT));
+            return LoginStatus::MfaRequired(username);
+        }
+        self.finish_login(index, now)
+    }
+
+    // The second step after MfaRequired: checks a one-time code from the
+    // user's authenticator app. Wrong codes count towards a lock, like wrong passwords.
+    pub fn login_with_code(&mut self, username: &str, code: &str) -> LoginStatus {
+        let now = (self.clock)();
+        let waiting = self
+            .waiting_for_code
+            .get(&username.to_lowercase())
+            .is_some_and(|&until| now < until);
+        let index = match self.position(username) {
+            Some(index) if waiting => index,
+            _ => return LoginStatus::Error(String::from("Log in with your password first")),
+        };
+        let user = &mut self.users[index];
+        if user.locked_until > now {
+            return LoginStatus::Locked(user.locked_until - now);
+        }
+        let step = match user.mfa_secret.as_deref().map(Totp::from_base32) {
+            Some(Ok(totp)) => totp.verify_at(code, now),
+            Some(Err(e)) => return LoginStatus::Error(format!("The one-time code secret is broken: {}", e)),
+            None => return LoginStatus::Error(String::from("This account doesn't use one-time codes")),
+        };
+        // A code can only be used once, even though it works for a whole time step.
+        match step {
+            Some(step) if user.last_code_step.is_none_or(|last| step > last) => {
+                user.last_code_step = Some(step);
+                self.waiting_for_code.remove(&username.to_lowercase());
+                self.finish_login(index, now)
+            }
+            _ => self.wrong_attempt(index, now, "Wrong or already used one-time code"),
+        }
+    }
+
+    fn wrong_attempt(&mut self, index: usize, now: u64, message: &str) -> LoginStatus {
+        let user = &mut self.users[index];
+        user.failed_attempts = user.failed_attempts.saturating_add(1);
+        match self.policy.lock_seconds(user.failed_attempts) {
+            Some(seconds) => {
+                user.locked_until = now.saturating_add(seconds);
+                LoginStatus::Locked(seconds)
+            }
+            None => LoginStatus::Error(message.to_string()),
+        }
+    }
+
+    // Everything checked out: clear the failed attempts and say hello, unless
+    // the password is too old.
+    fn finish_login(&mut self, index: usize, now: u64) -> LoginStatus {
+        let user = &mut self.users[index];
+        user.failed_attempts = 0;
+        user.locked_until = 0;
+        if let Some(max_age) = self.policy.max_password_age
+            && now.saturating_sub(user.password_changed) > max_age
+        {
+            return LoginStatus::Expired(user.username.clone());
+        }
+        LoginStatus::Success(format!("Welcome, {}!", user.username))
+    }
+
+    // Saves the users. Logging in changes the failed attempt counts, so save after that too.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        write_atomically(path, &self.to_json().pretty())
+    }
+
+    // Loads the users. A missing file gives an empty store.
+    pub fn load(path: &str) -> Result<UserStore, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(UserStore::new()),
+            Err(e) => return Err(format!("Could not read {}: {}", path, e)),
+        };
+        let value = json::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+        UserStore::from_json(&value).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+fn check_username(username: &str) -> Result<(), String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.';
+    if username.is_empty() || username.len() > 32 || !username.chars().all(allowed) {
+        return Err(format!(
+            "'{}' isn't a valid username; use 1 to 32 letters, digits, _, - or .",
+            username
+        ));
+    }
+    Ok(())
+}
+
+// Writes to "<path>.tmp" first, then renames it to `path`. A rename is a
+// single step for the file system, so anyone reading the file sees either
+// the whole old version or the whole new one, even if the program crashes.
+fn write_atomically(path: &str, text: &str) -> Result<(), String> {
+    let temp = format!("{}.tmp", path);
+    let write = || -> std::io::Result<()> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // On Linux and macOS, only the owner can read the file.
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&temp)?;
+        file.write_all(text.as_bytes())?;
+        // sync_all waits until the data is really on the disk, not just in a buffer.
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    };
+    write().map_err(|e| {
+        // Don't leave a half-written temporary file behind.
+        fs::remove_file(&temp).ok();
+        format!("Could not write {}: {}", path, e)
+    })
+}
+
+impl ToJson for User {
+    fn to_json(&self) -> JsonValue {
+        let mut fields = vec![
+            (String::from("username"), self.username.to_json()),
+            (String::from("password"), self.password.to_string().to_json()),
+            (String::from("password_changed"), self.password_changed.to_json()),
+            (String::from("failed_attempts"), self.failed_attempts.to_json()),
+            (String::from("locked_until"), self.locked_until.to_json()),
+        ];
+        if let Some(secret) = &self.mfa_secret {
+            fields.push((String::from("mfa_secret"), secret.to_json()));
+        }
+        if let Some(step) = self.last_code_step {
+            fields.push((String::from("last_code_step"), step.to_json()));
+        }
+        JsonValue::Object(fields)
+    }
+}
+
+impl FromJson for User {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        let password = String::from_json(value.field("password")?)?;
+        Ok(User {
+            username: String::from_json(value.field("username")?)?,
+            password: PasswordHash::parse(&password).map_err(|e| format!("password: {}", e))?,
+            password_changed: u64::from_json(value.field("password_changed")?)?,
+            failed_attempts: u32::from_json(value.field("failed_attempts")?)?,
+            locked_until: u64::from_json(value.field("locked_until")?)?,
+            mfa_secret: value.get("mfa_secret").map(String::from_json).transpose()?,
+            last_code_step: value.get("last_code_step").map(u64::from_json).transpose()?,
+        })
+    }
+}
+
+impl ToJson for UserStore {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![(String::from("users"), self.users.to_json())])
+    }
+}
+
+impl FromJson for UserStore {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        let mut store = UserStore::new();
+        let users: Vec<User> = Vec::from_json(value.field("users")?).map_err(|e| format!("users: {}", e))?;
+        // A hand-edited file could have the same user twice, as "ann" and "Ann". Logging in
+        // would only ever find the first one, so refuse the file instead.
+        for (i, user) in users.iter().enumerate() {
+            check_username(&user.username).map_err(|e| format!("users[{}]: {}", i, e))?;
+            if users[..i].iter().any(|u| u.username.eq_ignore_ascii_case(&user.username)) {
+                return Err(format!("users[{}]: the username {} is used twice", i, user.username));
+            }
+        }
+        store.users = users;
+        Ok(store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // Each test runs on its own thread, so each gets its own fake time.
+    thread_local! {
+        static NOW: Cell<u64> = const { Cell::new(1_000_000) };
+    }
+
+    fn fake_clock() -> u64 {
+        NOW.with(|now| now.get())
+    }
+
+    fn wait(seconds: u64) {
+        NOW.with(|now| now.set(now.get() + seconds));
+    }
+
+    // Tiny hash settings, so the tests don't spend their time hashing.
+    fn store() -> UserStore {
+        let mut store = UserStore::with_settings(HashSettings { space: 16, rounds: 1 });
+        store.set_clock(fake_clock);
+        store.add_user("ann", "password1").unwrap();
+        store
+    }
+
+    #[test]
+    fn lock_doubles_up_to_the_longest() {
+        let policy = Policy::default();
+        assert_eq!(policy.lock_seconds(2), None);
+        assert_eq!(policy.lock_seconds(3), Some(30));
+        assert_eq!(policy.lock_seconds(4), Some(60));
+        assert_eq!(policy.lock_seconds(5), Some(120));
+        assert_eq!(policy.lock_seconds(20), Some(3600));
+        assert_eq!(policy.lock_seconds(u32::MAX), Some(3600));
+    }
+
+    #[test]
+    fn wrong_passwords_lock_the_account() {
+        let mut store = store();
+        assert_eq!(store.login("ann", "nope"), LoginStatus::Error(String::from(WRONG_DETAILS)));
+        assert_eq!(store.login("ann", "nope"), LoginStatus::Error(String::from(WRONG_DETAILS)));
+        assert_eq!(store.login("ann", "nope"), LoginStatus::Locked(30));
+        // Even the right password is refused until the lock ends.
+        wait(10);
+        assert_eq!(store.login("ann", "password1"), LoginStatus::Locked(20));
+        wait(20);
+        assert_eq!(store.login("ann", "nope"), LoginStatus::Locked(60));
+        wait(60);
+        assert_eq!(store.login("ANN", "password1"), LoginStatus::Success(String::from("Welcome, ann!")));
+        assert_eq!(store.user("ann").map(|u| u.failed_attempts), Some(0));
+    }
+
+    #[test]
+    fn old_passwords_expire() {
+        let mut store = store();
+        store.policy.max_password_age = Some(100);
+        // A password exactly max_password_age old still works.
+        wait(100);
+        assert!(matches!(store.login("ann", "password1"), LoginStatus::Success(_)));
+        wait(1);
+        assert_eq!(store.login("ann", "password1"), LoginStatus::Expired(String::from("ann")));
+        store.change_password("ann", "password1", "password2").unwrap();
+        assert!(matches!(store.login("ann", "password2"), LoginStatus::Success(_)));
+    }
+
+    #[test]
+    fn loading_refuses_duplicate_or_bad_usernames() {
+        let mut store = store();
+        store.add_user("bob", "password1").unwrap();
+        assert!(UserStore::from_json(&store.to_json()).is_ok());
+
+        store.users[1].username = String::from("ANN");
+        let error = UserStore::from_json(&store.to_json()).err().unwrap();
+        assert_eq!(error, "users[1]: the username ANN is used twice");
+
+        store.users[1].username = String::from("bad name");
+        assert!(UserStore::from_json(&store.to_json()).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+// Converting between naming styles like snake_case, camelCase and PascalCase.
+//
+// Rust has a convention for each kind of name:
+// - variables and functions use snake_case (capital_cities)
+// - types like structs and enums use PascalCase (LoginStatus)
+// - constants use SCREAMING_SNAKE_CASE (MY_CONSTANT)
+// The compiler warns you when you break these, which is why `capitalCities`
+// in main.rs gets a warning. This module can work out the right spelling.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    Snake,          // snake_case
+    Camel,          // camelCase
+    Pascal,         // PascalCase
+    Kebab,          // kebab-case
+    ScreamingSnake, // SCREAMING_SNAKE_CASE
+    Title,          // Title Case
+}
+
+pub const ALL_CASES: [Case; 6] = [
+    Case::Snake,
+    Case::Camel,
+    Case::Pascal,
+    Case::Kebab,
+    Case::ScreamingSnake,
+    Case::Title,
+];
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Case::Snake => "snake_case",
+            Case::Camel => "camelCase",
+            Case::Pascal => "PascalCase",
+            Case::Kebab => "kebab-case",
+            Case::ScreamingSnake => "SCREAMING_SNAKE_CASE",
+            Case::Title => "Title Case",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Lets you write "snake".parse::<Case>(), which the case command uses.
+impl FromStr for Case {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "snake" | "snakecase" => Ok(Case::Snake),
+            "camel" | "camelcase" => Ok(Case::Camel),
+            "pascal" | "pascalcase" => Ok(Case::Pascal),
+            "kebab" | "kebabcase" => Ok(Case::Kebab),
+            "screaming" | "screamingsnake" | "screamingsnakecase" | "constant" => Ok(Case::ScreamingSnake),
+            "title" | "titlecase" => Ok(Case::Title),
+            _ => Err(format!(
+                "'{}' is not a case style; try snake, camel, pascal, kebab, screaming or title",
+                s
+            )),
+        }
+    }
+}
+
+// Splits a name into its words, whatever style it's written in:
+//
+//     "capitalCities"   => ["capital", "Cities"]
+//     "MY_CONSTANT"     => ["MY", "CONSTANT"]
+//     "HTTPServer"      => ["HTTP", "Server"]   (an acronym followed by a word)
+//     "utf8Encoding"    => ["utf8", "Encoding"] (digits stay with the word before them)
+pub fn split_words(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let chars: Vec<char> = name.chars().collect();
+
+    for (i, &c) in chars.iter().enumerate() {
+        // Anything that isn't a letter or digit (_ - space . etc.) separates words.
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !current.is_empty() {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // "capitalCities": a capital after a lowercase letter or digit starts a new word.
+            // "HTTPServer": a capital followed by a lowercase letter ends an acronym.
+            if previous.is_lowercase() || previous.is_numeric() || (previous.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+// "hELLO" => "Hello". to_uppercase() returns an iterator because some letters
+// become more than one letter, like the German ß => SS.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+        None => String::new(),
+    }
+}
+
+// An acronym is a word of two or more letters that are all capitals, like HTTP.
+fn is_acronym(word: &str) -> bool {
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    letters > 1 && !word.chars().any(|c| c.is_lowercase())
+}
+
+// Rewrites a name in another style, e.g. convert("capitalCities", Case::Snake) => "capital_cities".
+// Acronyms become normal words in camelCase and PascalCase ("HTTPServer" => "HttpServer"),
+// which is what the Rust style guide asks for, but stay in capitals in Title Case.
+pub fn convert(name: &str, case: Case) -> String {
+    let words = split_words(name);
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+
+    match case {
+        Case::Snake => lower.join("_"),
+        Case::Kebab => lower.join("-"),
+        Case::ScreamingSnake => words.iter().map(|w| w.to_uppercase()).collect::<Vec<_>>().join("_"),
+        Case::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+        Case::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_lowercase() } else { capitalize(w) })
+            .collect(),
+        Case::Title => words
+            .iter()
+            .map(|w| if is_acronym(w) { w.clone() } else { capitalize(w) })
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+// Whether a name is already written in a style.
+pub fn is_case(name: &str, case: Case) -> bool {
+    !name.is_empty() && convert(name, case) == name
+}
+
+// Works out which style a name is written in. A single lowercase word like "score"
+// counts as snake_case first, since that's the first style checked.
+pub fn detect(name: &str) -> Option<Case> {
+    ALL_CASES.into_iter().find(|case| is_case(name, *case))
+}
+
+// The kinds of name Rust has a convention for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NameKind {
+    Variable,
+    Type,
+    Constant,
+}
+
+impl NameKind {
+    pub fn expected_case(&self) -> Case {
+        match self {
+            NameKind::Variable => Case::Snake,
+            NameKind::Type => Case::Pascal,
+            NameKind::Constant => Case::ScreamingSnake,
+        }
+    }
+}
+
+// Checks a name against Rust's convention. Returns None if it's fine,
+// or Some(message) explaining the fix.
+pub fn check_rust_name(name: &str, kind: NameKind) -> Option<String> {
+    let expected = kind.expected_case();
+    if is_case(name, expected) {
+        return None;
+    }
+    let written_as = match detect(name) {
+        Some(case) => format!("is written in {}", case),
+        None => String::from("mixes styles"),
+    };
+    Some(format!(
+        "`{}` {}, but Rust expects {:?} names in {}: `{}`",
+        name,
+        written_as,
+        kind,
+        expected,
+        convert(name, expected)
+    ))
+}
@@ -0,0 +1,942 @@
+// Command line commands.
+//
+// Running `cargo run` on its own runs the lessons in main.rs.
+// Anything you type after `--` is passed to the program as arguments,
+// so `cargo run -- grade 85` calls run_command with ["grade", "85"].
+
+use crate::utils::grading::GradingScale;
+use crate::utils::gradebook::{percentile, Curve, Gradebook};
+use crate::utils::csv;
+use crate::utils::json;
+use crate::utils::text_stats;
+use crate::utils::case_convert::{self, Case, NameKind, ALL_CASES};
+use crate::utils::template::{self, Value};
+use crate::utils::fuzzy;
+use crate::utils::topics;
+use crate::utils::shopping_list::{ListManager, SortBy};
+use crate::utils::grid::{self, Edges, Grid, Position};
+use crate::utils::maze::{Generator, Maze, Solver};
+use crate::utils::life::{self, Pattern, Rule, World};
+use crate::utils::turtle;
+use crate::utils::auth::{self, UserStore};
+use crate::utils::rbac::{AccessPolicy, Subject};
+use crate::utils::totp::{Algorithm, Totp};
+use crate::utils::people::{PersonField, PersonRegistry, Record};
+use crate::LoginStatus;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::io::Read;
+use std::fs;
+
+// Used to suggest a command when you mistype one.
+const COMMAND_NAMES: [&str; 19] = [
+    "grade", "gradebook", "csv", "json", "textstats", "case", "template", "topic", "topics", "list", "move", "maze",
+    "life", "turtle", "user", "access", "totp", "people", "help",
+];
+
+const USAGE: &str = "Commands:
+  grade <score> [--scale <file>]        Show the letter grade for a score
+  gradebook <action> [--file <path>]    Manage the gradebook (default file: gradebook.txt)
+      add-student <name>                remove-student <name>
+      add-assignment <name> <weight> [max score]
+      remove-assignment <name>
+      score <student> <assignment> <score>
+      remove-score <student> <assignment>
+      report                            stats
+      curve add <points> | curve top | curve mean <target>
+      import <file.csv>                 export <file.csv>
+  csv scores <file.csv>                 Load a score table with name and score columns
+  csv people <file.csv>                 Load a list of people with name and age columns
+  json <file.json>                      Check a JSON file and print it neatly
+  textstats [file] [--top <n>]          Count bytes, chars, words and lines (reads stdin without a file)
+  case <style> <text>                   Convert to snake, camel, pascal, kebab, screaming or title
+  case all <text>                       Show the text in every style
+  case check <variable|type|constant> <name>   Check a name follows Rust's naming rules
+  template <file> [data.json] [--set name=value]...   Fill in a template
+  topic <name>                          Show a summary of a lesson topic
+  topics                                List the lesson topics
+  list <action> [--file <path>]         Manage shopping lists (default file: lists.json)
+      show [list]                       new <list>     delete <list>     rename <list> <new name>
+      add <list> <item> [quantity] [category]          remove <list> <item>
+      rename-item <list> <item> <new name>             clear <list>
+      move <list> <item> <position>     move-to <list> <item> <other list>
+      sort <list> [name|category|quantity]             dedupe <list>
+      undo                              redo
+  move <script> [--size <w>x<h>] [--start <x>,<y>] [--wrap]
+                                        Follow moves like \"UUR 3D (DL)\" and draw the path
+  maze [--size <w>x<h>] [--algorithm backtracker|kruskal] [--solve bfs|dfs|astar|all] [--seed <n>]
+                                        Make a random maze, and solve it from the top left to the bottom right
+  life <pattern or file> [--generations <n>] [--size <w>x<h>] [--wrap] [--rule B3/S23] [--delay <ms>]
+                                        Run the Game of Life on a .rle or .cells file, or one of:
+                                        block, beehive, blinker, toad, pulsar, glider
+  turtle <file> [--out <file.svg>]      Run a turtle graphics program and save the drawing (default: turtle.svg)
+  user <action> [--store <path>]        Manage user accounts (default file: users.json)
+      add <name>                        login <name>       passwd <name>
+      remove <name>                     unlock <name>      show
+      mfa <name>                        mfa-off <name>
+                                        Passwords and one-time codes are read from stdin, one per line
+  access <policy file> <action> [--role <name>]... [--set name=value]...
+                                        Check whether a policy allows an action, and say which rule decided
+  totp <base32 secret> [--digits <6-8>] [--algorithm sha1|sha256]
+                                        Show the current one-time code for a secret, like an authenticator app
+  people <action> [--file <path>]       Manage a registry of people (default file: people.json)
+      show [--sort id|name|age] [--desc]                add <name> <age>
+      update <id> [--name <name>] [--age <age>]         delete <id>
+      find <name>                       search <text>     ages <min> <max>
+      import <file.csv>                 (name and age columns)";
+
+pub fn run_command(args: &[String]) -> Result<(), String> {
+    // args[0] is the command name and the rest are its arguments.
+    let rest = &args[1..];
+    match args[0].as_str() {
+        "grade" => grade_command(rest),
+        "gradebook" => gradebook_command(rest),
+        "csv" => csv_command(rest),
+        "json" => json_command(rest),
+        "textstats" => textstats_command(rest),
+        "case" => case_command(rest),
+        "template" => template_command(rest),
+        "topic" => topic_command(rest),
+        "list" => list_command(rest),
+        "move" => move_command(rest),
+        "maze" => maze_command(rest),
+        "life" => life_command(rest),
+        "turtle" => turtle_command(rest),
+        "user" => user_command(rest),
+        "access" => access_command(rest),
+        "totp" => totp_command(rest),
+        "people" => people_command(rest),
+        "topics" => {
+            println!("{}", topics::topic_names().join("\n"));
+            Ok(())
+        }
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => match fuzzy::did_you_mean(other, &COMMAND_NAMES) {
+            Some(suggestion) => Err(format!("Unknown command '{}'. Did you mean '{}'?", other, suggestion)),
+            None => Err(format!("Unknown command '{}'\n{}", other, USAGE)),
+        },
+    }
+}
+
+// Looks for `--name value` in the arguments and returns the value.
+fn option_value<'a>(args: &'a [String], name: &str) -> Result<Option<&'a str>, String> {
+    match args.iter().position(|a| a == name) {
+        Some(i) => match args.get(i + 1) {
+            Some(value) => Ok(Some(value.as_str())),
+            None => Err(format!("{} needs a value after it", name)),
+        },
+        None => Ok(None),
+    }
+}
+
+fn grade_command(args: &[String]) -> Result<(), String> {
+    let score_text = args
+        .first()
+        .ok_or_else(|| String::from("Usage: grade <score> [--scale <file.toml or file.json>]"))?;
+    let score: f64 = score_text
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", score_text))?;
+
+    let scale = match option_value(args, "--scale")? {
+        Some(path) => GradingScale::load(path)?,
+        None => GradingScale::default(),
+    };
+
+    println!("{} => {}", score, scale.grade(score)?);
+    Ok(())
+}
+
+// Removes `--name value` from the arguments, returning the value and the other arguments.
+fn take_option(args: &[String], name: &str) -> Result<(Option<String>, Vec<String>), String> {
+    let value = option_value(args, name)?.map(|v| v.to_string());
+    let mut others: Vec<String> = Vec::new();
+    let mut skip_next = false;
+    for arg in args {
+        if skip_next {
+            skip_next = false;
+        } else if arg == name {
+            skip_next = true;
+        } else {
+            others.push(arg.clone());
+        }
+    }
+    Ok((value, others))
+}
+
+fn parse_number(text: &str) -> Result<f64, String> {
+    text.parse().map_err(|_| format!("'{}' is not a number", text))
+}
+
+fn gradebook_command(args: &[String]) -> Result<(), String> {
+    let (file, args) = take_option(args, "--file")?;
+    let path = file.unwrap_or_else(|| String::from("gradebook.txt"));
+    let mut book = Gradebook::load(&path)?;
+
+    // Turn the Vec<String> into a Vec<&str> so we can match on string literals.
+    let words: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    let changed = match words.as_slice() {
+        ["add-student", name] => book.add_student(name).map(|_| true)?,
+        ["remove-student", name] => book.remove_student(name).map(|_| true)?,
+        ["add-assignment", name, weight] => {
+            book.add_assignment(name, parse_number(weight)?, 100.0)?;
+            true
+        }
+        ["add-assignment", name, weight, max] => {
+            book.add_assignment(name, parse_number(weight)?, parse_number(max)?)?;
+            true
+        }
+        ["remove-assignment", name] => book.remove_assignment(name).map(|_| true)?,
+        ["score", student, assignment, score] => {
+            book.set_score(student, assignment, parse_number(score)?)?;
+            true
+        }
+        ["remove-score", student, assignment] => book.remove_score(student, assignment).map(|_| true)?,
+        ["report"] => {
+            print_report(&book);
+            false
+        }
+        ["stats"] => {
+            print_stats(&book);
+            false
+        }
+        ["curve", "add", points] => {
+            print_curve(&book, &Curve::Add(parse_number(points)?));
+            false
+        }
+        ["curve", "top"] => {
+            print_curve(&book, &Curve::TopToHundred);
+            false
+        }
+        ["curve", "mean", target] => {
+            print_curve(&book, &Curve::TargetMean(parse_number(target)?));
+            false
+        }
+        ["import", file] => {
+            let text = read_file(file)?;
+            let count = book.import_csv(&text).map_err(|e| format!("{}: {}", file, e))?;
+            println!("Imported {} scores from {}", count, file);
+            true
+        }
+        ["export", file] => {
+            fs::write(file, book.export_csv()).map_err(|e| format!("Could not write {}: {}", file, e))?;
+            println!("Exported the gradebook to {}", file);
+            false
+        }
+        _ => return Err(format!("Unknown gradebook action\n{}", USAGE)),
+    };
+
+    // Only write the file back if something changed.
+    if changed {
+        book.save(&path)?;
+        println!("Saved {}", path);
+    }
+    Ok(())
+}
+
+fn print_report(book: &Gradebook) {
+    let scale = GradingScale::default();
+    if book.students.is_empty() {
+        println!("The gradebook has no students yet.");
+        return;
+    }
+    for (rank, name, average) in book.ranking() {
+        let grade = scale.grade(average).map(|g| g.to_string()).unwrap_or_default();
+        println!("{:>3}. {:<20} {:>6.2}%  {}", rank, name, average, grade);
+    }
+    for name in &book.students {
+        if book.weighted_average(name).is_none() {
+            println!("     {:<20} no scores yet", name);
+        }
+    }
+}
+
+fn print_stats(book: &Gradebook) {
+    let Some(stats) = book.class_stats() else {
+        println!("No scores yet.");
+        return;
+    };
+    let mut averages: Vec<f64> = book.averages().into_iter().map(|(_, avg)| avg).collect();
+    averages.sort_by(|a, b| a.total_cmp(b));
+
+    println!("Students:  {}", stats.count);
+    println!("Mean:      {:.2}", stats.mean);
+    println!("Median:    {:.2}", stats.median);
+    println!("Std dev:   {:.2}", stats.std_dev);
+    println!("Min / max: {:.2} / {:.2}", stats.min, stats.max);
+    for p in [25.0, 75.0, 90.0] {
+        println!("{}th percentile: {:.2}", p, percentile(&averages, p));
+    }
+}
+
+fn print_curve(book: &Gradebook, curve: &Curve) {
+    let scale = GradingScale::default();
+    let before: Vec<(String, f64)> = book.averages();
+    for ((name, old), (_, new)) in before.iter().zip(book.curved_averages(curve)) {
+        let grade = scale.grade(new).map(|g| g.to_string()).unwrap_or_default();
+        println!("{:<20} {:>6.2}% => {:>6.2}%  {}", name, old, new, grade);
+    }
+}
+
+fn read_file(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))
+}
+
+fn csv_command(args: &[String]) -> Result<(), String> {
+    let words: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    match words.as_slice() {
+        ["scores", file] => {
+            let scores = csv::load_scores(&read_file(file)?).map_err(|e| format!("{}: {}", file, e))?;
+            // A HashMap has no order, so sort the names before printing.
+            let mut names: Vec<&String> = scores.keys().collect();
+            names.sort();
+            for name in names {
+                println!("{}: {}", name, scores[name]);
+            }
+        }
+        ["people", file] => {
+            let people = csv::load_people(&read_file(file)?).map_err(|e| format!("{}: {}", file, e))?;
+            for person in people {
+                println!("{} is {} years old", person.name, person.age);
+            }
+        }
+        _ => return Err(format!("Unknown csv action\n{}", USAGE)),
+    }
+    Ok(())
+}
+
+fn json_command(args: &[String]) -> Result<(), String> {
+    let file = args.first().ok_or_else(|| String::from("Usage: json <file.json>"))?;
+    let value = json::parse(&read_file(file)?).map_err(|e| format!("{}: {}", file, e))?;
+    println!("{}", value.pretty());
+    Ok(())
+}
+
+fn textstats_command(args: &[String]) -> Result<(), String> {
+    let (top, args) = take_option(args, "--top")?;
+    let top: usize = match top {
+        Some(n) => n.parse().map_err(|_| format!("'{}' is not a whole number", n))?,
+        None => 10,
+    };
+
+    let text = match args.first().map(|a| a.as_str()) {
+        Some("-") | None => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|e| format!("Could not read stdin: {}", e))?;
+            text
+        }
+        Some(file) => read_file(file)?,
+    };
+
+    print!("{}", text_stats::analyze(&text).report(top));
+    Ok(())
+}
+
+fn case_command(args: &[String]) -> Result<(), String> {
+    if args.len() < 2 {
+        return Err(format!("Usage: case <style> <text>\n{}", USAGE));
+    }
+    // Everything after the style is the text, so `case snake Hello World` works without quotes.
+    let text = args[1..].join(" ");
+    match args[0].as_str() {
+        "all" => {
+            for case in ALL_CASES {
+                println!("{:<22} {}", case.to_string(), case_convert::convert(&text, case));
+            }
+        }
+        "check" => {
+            let kind = match args[1].as_str() {
+                "variable" | "function" => NameKind::Variable,
+                "type" | "struct" | "enum" => NameKind::Type,
+                "constant" | "const" => NameKind::Constant,
+                other => return Err(format!("'{}' should be variable, type or constant", other)),
+            };
+            let name = args.get(2).ok_or_else(|| String::from("Usage: case check <kind> <name>"))?;
+            match case_convert::check_rust_name(name, kind) {
+                Some(advice) => println!("{}", advice),
+                None => println!("`{}` follows the Rust naming rules for a {:?}", name, kind),
+            }
+        }
+        style => {
+            let case: Case = style.parse()?;
+            println!("{}", case_convert::convert(&text, case));
+        }
+    }
+    Ok(())
+}
+
+fn template_command(args: &[String]) -> Result<(), String> {
+    let mut values: HashMap<String, Value> = HashMap::new();
+    let mut files: Vec<&String> = Vec::new();
+
+    // --set can be given many times, so we can't use take_option here.
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "--set" {
+            let pair = args.get(i + 1).ok_or_else(|| String::from("--set needs name=value after it"))?;
+            let (name, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("'{}' should look like name=value", pair))?;
+            values.insert(name.to_string(), Value::from(value));
+            i += 2;
+        } else {
+            files.push(&args[i]);
+            i += 1;
+        }
+    }
+
+    let template_file = files
+        .first()
+        .ok_or_else(|| String::from("Usage: template <file> [data.json] [--set name=value]"))?;
+    if let Some(data_file) = files.get(1) {
+        let data = json::parse(&read_file(data_file)?).map_err(|e| format!("{}: {}", data_file, e))?;
+        match Value::from(&data) {
+            // --set values win over values from the file.
+            Value::Map(map) => {
+                for (name, value) in map {
+                    values.entry(name).or_insert(value);
+                }
+            }
+            _ => return Err(format!("{} must contain a JSON object", data_file)),
+        }
+    }
+
+    let text = template::render(&read_file(template_file)?, &values).map_err(|e| format!("{}: {}", template_file, e))?;
+    print!("{}", text);
+    Ok(())
+}
+
+fn topic_command(args: &[String]) -> Result<(), String> {
+    let name = args.join(" ");
+    if name.is_empty() {
+        return Err(String::from("Usage: topic <name> (see `topics` for the list)"));
+    }
+    if let Some(summary) = topics::find_topic(&name) {
+        println!("{}: {}", name, summary);
+        return Ok(());
+    }
+
+    let names = topics::topic_names();
+    let close = fuzzy::suggestions(&name, &names, 3);
+    if close.is_empty() {
+        Err(format!("There is no topic called '{}'. Run `topics` to see them all.", name))
+    } else {
+        Err(format!("There is no topic called '{}'. Did you mean: {}?", name, close.join(", ")))
+    }
+}
+
+fn list_command(args: &[String]) -> Result<(), String> {
+    let (file, args) = take_option(args, "--file")?;
+    let path = file.unwrap_or_else(|| String::from("lists.json"));
+    let mut manager = ListManager::load(&path)?;
+
+    let parse_count = |text: &str| -> Result<u32, String> {
+        text.parse().map_err(|_| format!("'{}' is not a whole number", text))
+    };
+
+    let words: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    match words.as_slice() {
+        [] | ["show"] => {
+            if manager.lists.is_empty() {
+                println!("There are no lists yet. Make one with `list new <name>`.");
+            }
+            for list in &manager.lists {
+                print_list(&manager, &list.name)?;
+            }
+            return Ok(());
+        }
+        ["show", list] => return print_list(&manager, list),
+        ["new", list] => manager.create_list(list)?,
+        ["delete", list] => manager.delete_list(list)?,
+        ["rename", list, new_name] => manager.rename_list(list, new_name)?,
+        ["add", list, item] => manager.add_item(list, item, 1, "")?,
+        ["add", list, item, quantity] => manager.add_item(list, item, parse_count(quantity)?, "")?,
+        ["add", list, item, quantity, category] => manager.add_item(list, item, parse_count(quantity)?, category)?,
+        ["remove", list, item] => manager.remove_item(list, item)?,
+        ["rename-item", list, item, new_name] => manager.rename_item(list, item, new_name)?,
+        ["move", list, item, position] => manager.move_item(list, item, parse_count(position)? as usize)?,
+        ["move-to", list, item, other] => manager.move_to_list(list, item, other)?,
+        ["sort", list] => manager.sort_list(list, SortBy::Name)?,
+        ["sort", list, by] => {
+            let by = match *by {
+                "name" => SortBy::Name,
+                "category" => SortBy::Category,
+                "quantity" => SortBy::Quantity,
+                other => return Err(format!("Can't sort by '{}'; use name, category or quantity", other)),
+            };
+            manager.sort_list(list, by)?
+        }
+        ["dedupe", list] => {
+            let merged = manager.dedupe_list(list)?;
+            println!("Merged {} duplicate item(s)", merged);
+        }
+        ["clear", list] => manager.clear_list(list)?,
+        ["undo"] => manager.undo()?,
+        ["redo"] => manager.redo()?,
+        _ => return Err(format!("Unknown list action\n{}", USAGE)),
+    }
+
+    manager.save(&path)?;
+    // Show the result of the change, or every list after undo and redo.
+    match words.get(1) {
+        Some(list) if manager.list(list).is_some() => print_list(&manager, list),
+        _ => {
+            for list in &manager.lists {
+                print_list(&manager, &list.name)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_list(manager: &ListManager, name: &str) -> Result<(), String> {
+    let list = manager.list(name).ok_or_else(|| format!("There is no list called {}", name))?;
+    println!("{} ({} items)", list.name, list.items.len());
+    for (i, item) in list.items.iter().enumerate() {
+        let category = if item.category.is_empty() {
+            String::new()
+        } else {
+            format!("  [{}]", item.category)
+        };
+        println!("  {}. {} x{}{}", i + 1, item.name, item.quantity, category);
+    }
+    Ok(())
+}
+
+// Parses "3x4" into (3, 4), or "1,2" into (1, 2), depending on the separator.
+fn parse_pair<T: std::str::FromStr>(text: &str, separator: char) -> Result<(T, T), String> {
+    let error = || format!("'{}' should be two whole numbers separated by '{}'", text, separator);
+    let (a, b) = text.split_once(separator).ok_or_else(error)?;
+    Ok((a.trim().parse().map_err(|_| error())?, b.trim().parse().map_err(|_| error())?))
+}
+
+fn move_command(args: &[String]) -> Result<(), String> {
+    let (size, args) = take_option(args, "--size")?;
+    let (start, args) = take_option(&args, "--start")?;
+    // --wrap is a flag on its own, with no value after it.
+    let wrap = args.iter().any(|a| a == "--wrap");
+    let script_words: Vec<&str> = args.iter().map(|a| a.as_str()).filter(|a| *a != "--wrap").collect();
+    if script_words.is_empty() {
+        return Err(String::from("Usage: move <script> [--size <w>x<h>] [--start <x>,<y>] [--wrap]"));
+    }
+    // The script can be one quoted argument or several words, so `move UUR DDL` works too.
+    let moves = grid::parse_moves(&script_words.join(" "))?;
+
+    let edges = if wrap { Edges::Wrapping } else { Edges::Bounded };
+    let (width, height, start) = match size {
+        Some(size) => {
+            let (width, height) = parse_pair::<usize>(&size, 'x')?;
+            let start = match start {
+                Some(start) => {
+                    let (x, y) = parse_pair::<i32>(&start, ',')?;
+                    Position::new(x, y)
+                }
+                // Without --start, begin in the middle.
+                None => Position::new(width as i32 / 2, height as i32 / 2),
+            };
+            (width, height, start)
+        }
+        None if wrap => return Err(String::from("--wrap needs a --size, so there are edges to wrap round")),
+        None if start.is_some() => return Err(String::from("--start needs a --size")),
+        // Without --size, the grid is made just big enough for the path.
+        None => grid::fit_path(&moves),
+    };
+
+    let mut board = Grid::new(width, height, '.', edges)?;
+    let path = board.walk(start, &moves)?;
+    board.draw_path(&path, &moves);
+    print!("{}", board);
+    let end = path[path.len() - 1];
+    println!(
+        "{} move(s) from {} to {} ({} straight steps apart)",
+        moves.len(),
+        start,
+        end,
+        start.manhattan_distance(end)
+    );
+    Ok(())
+}
+
+fn maze_command(args: &[String]) -> Result<(), String> {
+    let (size, args) = take_option(args, "--size")?;
+    let (algorithm, args) = take_option(&args, "--algorithm")?;
+    let (solve, args) = take_option(&args, "--solve")?;
+    let (seed, args) = take_option(&args, "--seed")?;
+    if let Some(extra) = args.first() {
+        return Err(format!("Unexpected '{}'\n{}", extra, USAGE));
+    }
+
+    let (width, height) = match size {
+        Some(size) => parse_pair::<usize>(&size, 'x')?,
+        None => (10, 6),
+    };
+    let generator = match algorithm.as_deref() {
+        None | Some("backtracker") => Generator::Backtracker,
+        Some("kruskal") => Generator::Kruskal,
+        Some(other) => return Err(format!("Unknown algorithm '{}'; use backtracker or kruskal", other)),
+    };
+    let solvers = match solve.as_deref() {
+        None => vec![],
+        Some("bfs") => vec![Solver::Bfs],
+        Some("dfs") => vec![Solver::Dfs],
+        Some("astar") => vec![Solver::AStar],
+        Some("all") => vec![Solver::Bfs, Solver::Dfs, Solver::AStar],
+        Some(other) => return Err(format!("Unknown solver '{}'; use bfs, dfs, astar or all", other)),
+    };
+
+    // The same seed always makes the same maze. Without one, every maze is different.
+    let maze = match seed {
+        Some(seed) => {
+            let seed: u64 = seed.parse().map_err(|_| format!("'{}' is not a whole number", seed))?;
+            Maze::generate(generator, width, height, &mut StdRng::seed_from_u64(seed))?
+        }
+        None => Maze::generate(generator, width, height, &mut rand::rng())?,
+    };
+
+    let mut route = None;
+    for solver in solvers {
+        let solution = maze
+            .solve(maze.entrance(), maze.exit(), solver)
+            .ok_or_else(|| String::from("The maze has no way through"))?;
+        println!(
+            "{}: {} steps, looked at {} of {} cells",
+            solver,
+            solution.route.len(),
+            solution.explored,
+            width * height
+        );
+        route = Some(solution.route);
+    }
+    print!("{}", maze.render(route.as_deref()));
+    Ok(())
+}
+
+fn life_command(args: &[String]) -> Result<(), String> {
+    let (generations, args) = take_option(args, "--generations")?;
+    let (size, args) = take_option(&args, "--size")?;
+    let (rule, args) = take_option(&args, "--rule")?;
+    let (delay, args) = take_option(&args, "--delay")?;
+    let wrap = args.iter().any(|a| a == "--wrap");
+    let args: Vec<&String> = args.iter().filter(|a| *a != "--wrap").collect();
+
+    let source = match args.as_slice() {
+        [source] => source.as_str(),
+        _ => return Err(String::from("Usage: life <pattern or file> [--generations <n>] [--size <w>x<h>] [--wrap] [--rule B3/S23] [--delay <ms>]")),
+    };
+    let pattern = match life::built_in_pattern(source) {
+        Some(pattern) => pattern,
+        None => {
+            let names: Vec<&str> = life::BUILT_IN_PATTERNS.iter().map(|(name, _)| *name).collect();
+            let text = read_file(source).map_err(|e| format!("{}. The built-in patterns are {}", e, names.join(", ")))?;
+            Pattern::parse(&text).map_err(|e| format!("{}: {}", source, e))?
+        }
+    };
+
+    let parse_whole = |text: &str| -> Result<u64, String> {
+        text.parse().map_err(|_| format!("'{}' is not a whole number", text))
+    };
+    let generations = match generations {
+        Some(n) => parse_whole(&n)?,
+        None => 4,
+    };
+    let delay = match delay {
+        Some(ms) => Some(std::time::Duration::from_millis(parse_whole(&ms)?)),
+        None => None,
+    };
+    // --rule wins over a rule in the file, and Conway's rule is used if neither gives one.
+    let rule = match rule {
+        Some(rule) => Rule::parse(&rule)?,
+        None => pattern.rule.unwrap_or_else(Rule::conway),
+    };
+    let edges = if wrap { Edges::Wrapping } else { Edges::Bounded };
+
+    let mut world = match size {
+        Some(size) => {
+            let (width, height) = parse_pair::<usize>(&size, 'x')?;
+            let mut world = World::new(width, height, rule, edges)?;
+            // Put the pattern in the middle.
+            let corner = Position::new(
+                (width as i32 - pattern.width as i32) / 2,
+                (height as i32 - pattern.height as i32) / 2,
+            );
+            world.place(&pattern, corner)?;
+            world
+        }
+        None => World::around(&pattern, 3, rule, edges)?,
+    };
+
+    for frame in 0..=generations {
+        if frame > 0 {
+            world.step();
+        }
+        if let Some(delay) = delay {
+            // \x1b[2J clears the terminal and \x1b[H moves the cursor to the top, so each
+            // frame is drawn over the last one like an animation.
+            print!("\x1b[2J\x1b[H");
+            std::thread::sleep(delay);
+        }
+        println!("Generation {} ({}), population {}", world.generation(), world.rule(), world.population());
+        print!("{}", world);
+    }
+    Ok(())
+}
+
+fn turtle_command(args: &[String]) -> Result<(), String> {
+    let (out, args) = take_option(args, "--out")?;
+    let path = match args.as_slice() {
+        [path] => path,
+        _ => return Err(String::from("Usage: turtle <file> [--out <file.svg>]")),
+    };
+    let out = out.unwrap_or_else(|| String::from("turtle.svg"));
+
+    let drawing = turtle::draw(&read_file(path)?).map_err(|e| format!("{}: {}", path, e))?;
+    drawing.save_svg(&out)?;
+    println!("Drew {} line(s) and saved the picture to {}", drawing.lines.len(), out);
+    Ok(())
+}
+
+// Reads one line from stdin. Passwords are read this way rather than taken
+// as arguments, because arguments end up in the shell history.
+fn read_password(prompt: &str) -> Result<String, String> {
+    eprint!("{}: ", prompt);
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| format!("Could not read stdin: {}", e))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn user_command(args: &[String]) -> Result<(), String> {
+    let (store, args) = take_option(args, "--store")?;
+    let path = store.unwrap_or_else(|| String::from("users.json"));
+    let mut users = UserStore::load(&path)?;
+
+    let words: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    match words.as_slice() {
+        [] | ["show"] => {
+            if users.users().is_empty() {
+                println!("There are no users yet. Add one with `user add <name>`.");
+            }
+            for user in users.users() {
+                let locked = if user.locked_until > auth::now_seconds() { " (locked)" } else { "" };
+                println!("{}: {} failed attempt(s){}", user.username, user.failed_attempts, locked);
+            }
+            return Ok(());
+        }
+        ["add", name] => {
+            let password = read_password("New password")?;
+            users.add_user(name, &password)?;
+            println!("Added {}", name);
+        }
+        ["remove", name] => {
+            users.remove_user(name)?;
+            println!("Removed {}", name);
+        }
+        ["unlock", name] => {
+            users.unlock(name)?;
+            println!("Unlocked {}", name);
+        }
+        ["mfa", name] => {
+            let totp = users.enable_mfa(name)?;
+            println!("One-time codes are now needed for {}. Add this secret to an authenticator app:", name);
+            println!("{}", totp.secret_base32());
+            println!("or open this link on your phone (or turn it into a QR code):");
+            println!("{}", totp.uri("Rust Lessons", name));
+        }
+        ["mfa-off", name] => {
+            users.set_mfa_secret(name, None)?;
+            println!("One-time codes are no longer needed for {}", name);
+        }
+        ["passwd", name] => {
+            let old_password = read_password("Old password")?;
+            let new_password = read_password("New password")?;
+            // Even a failed change is saved, because it counts as a wrong password.
+            let result = users.change_password(name, &old_password, &new_password);
+            users.save(&path)?;
+            result?;
+            println!("Changed the password for {}", name);
+            return Ok(());
+        }
+        ["login", name] => {
+            let password = read_password("Password")?;
+            let mut status = users.login(name, &password);
+            if let LoginStatus::MfaRequired(username) = &status {
+                let code = read_password("One-time code")?;
+                status = users.login_with_code(username, &code);
+            }
+            users.save(&path)?;
+            return match status {
+                LoginStatus::Success(message) => {
+                    println!("{}", message);
+                    Ok(())
+                }
+                LoginStatus::Error(message) => Err(message),
+                LoginStatus::Locked(seconds) => Err(format!("Too many wrong passwords; try again in {} second(s)", seconds)),
+                LoginStatus::Expired(username) => {
+                    Err(format!("The password has expired; change it with `user passwd {}`", username))
+                }
+                // login_with_code never asks for a second code, but the match has to cover every variant.
+                LoginStatus::MfaRequired(username) => Err(format!("{} needs a one-time code as well", username)),
+            };
+        }
+        _ => return Err(format!("Unknown user action\n{}", USAGE)),
+    }
+    users.save(&path)
+}
+
+fn access_command(args: &[String]) -> Result<(), String> {
+    let mut subject = Subject::default();
+    let mut words: Vec<&String> = Vec::new();
+
+    // --role and --set can be given many times, like template's --set.
+    let mut i = 0;
+    while i < args.len() {
+        let option = args[i].as_str();
+        if option == "--role" || option == "--set" {
+            let value = args.get(i + 1).ok_or_else(|| format!("{} needs a value after it", option))?;
+            if option == "--role" {
+                subject.roles.push(value.to_string());
+            } else {
+                let (name, value) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("'{}' should look like name=value", value))?;
+                // true, false and numbers are read as those, so rules can compare them.
+                let value = match value {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    _ => value.parse().map(Value::Number).unwrap_or_else(|_| Value::from(value)),
+                };
+                subject.attributes.insert(name.to_string(), value);
+            }
+            i += 2;
+        } else {
+            words.push(&args[i]);
+            i += 1;
+        }
+    }
+
+    let [policy_file, action] = words.as_slice() else {
+        return Err(String::from(
+            "Usage: access <policy file> <action> [--role <name>]... [--set name=value]...",
+        ));
+    };
+    let policy = AccessPolicy::load(policy_file)?;
+    if let Some(unknown) = subject.roles.iter().find(|role| !policy.roles().any(|r| &r.name == *role)) {
+        return Err(format!("{} doesn't define a role called {}", policy_file, unknown));
+    }
+    println!("{}", policy.check(&subject, action));
+    Ok(())
+}
+
+fn totp_command(args: &[String]) -> Result<(), String> {
+    let (digits, args) = take_option(args, "--digits")?;
+    let (algorithm, args) = take_option(&args, "--algorithm")?;
+    let secret = match args.as_slice() {
+        [secret] => secret,
+        _ => return Err(String::from("Usage: totp <base32 secret> [--digits <6-8>] [--algorithm sha1|sha256]")),
+    };
+
+    let mut totp = Totp::from_base32(secret)?;
+    if let Some(digits) = digits {
+        totp.set_digits(digits.parse().map_err(|_| format!("'{}' is not a whole number", digits))?)?;
+    }
+    if let Some(algorithm) = algorithm {
+        totp.algorithm = algorithm.parse::<Algorithm>()?;
+    }
+    let now = auth::now_seconds();
+    let seconds_left = totp.period - now % totp.period;
+    println!("{} (changes in {} second(s))", totp.code_at(now), seconds_left);
+    Ok(())
+}
+
+fn people_command(args: &[String]) -> Result<(), String> {
+    let (file, args) = take_option(args, "--file")?;
+    let path = file.unwrap_or_else(|| String::from("people.json"));
+    let mut registry = PersonRegistry::load(&path)?;
+
+    let parse_id = |text: &str| -> Result<u32, String> {
+        text.parse().map_err(|_| format!("'{}' is not an id", text))
+    };
+    let parse_age = |text: &str| -> Result<u32, String> {
+        text.parse().map_err(|_| format!("'{}' is not an age", text))
+    };
+
+    let words: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    match words.first().copied() {
+        None | Some("show") => {
+            let (sort, rest) = take_option(&args, "--sort")?;
+            let descending = rest.iter().any(|a| a == "--desc");
+            let by: PersonField = sort.as_deref().unwrap_or("id").parse()?;
+            if registry.is_empty() {
+                println!("There is nobody in {} yet. Add someone with `people add <name> <age>`.", path);
+            } else {
+                print_people(&registry.sorted(by, descending));
+            }
+            return Ok(());
+        }
+        Some("update") => {
+            let (name, rest) = take_option(&args, "--name")?;
+            let (age, rest) = take_option(&rest, "--age")?;
+            let id = match rest.as_slice() {
+                [_, id] => parse_id(id)?,
+                _ => return Err(String::from("Usage: people update <id> [--name <name>] [--age <age>]")),
+            };
+            if name.is_none() && age.is_none() {
+                return Err(String::from("Give a --name, an --age, or both"));
+            }
+            let age = age.as_deref().map(parse_age).transpose()?;
+            registry.update(id, name.as_deref(), age)?;
+            println!("Updated #{}", id);
+        }
+        _ => match words.as_slice() {
+            ["add", name, age] => {
+                let id = registry.add(name, parse_age(age)?)?;
+                println!("Added {} as #{}", name.trim(), id);
+            }
+            ["delete", id] => {
+                let person = registry.delete(parse_id(id)?)?;
+                println!("Deleted {}", person.name);
+            }
+            ["find", name] => {
+                print_people(&registry.find_by_name(name));
+                return Ok(());
+            }
+            ["search", text] => {
+                print_people(&registry.search(text));
+                return Ok(());
+            }
+            ["ages", min, max] => {
+                print_people(&registry.age_range(parse_age(min)?..=parse_age(max)?));
+                return Ok(());
+            }
+            ["import", file] => {
+                let people = csv::load_people_with_lines(&read_file(file)?).map_err(|e| format!("{}: {}", file, e))?;
+                // Check everyone first, so a bad row doesn't leave half the file imported.
+                let mut imported = registry.clone();
+                for (line, person) in &people {
+                    imported
+                        .add(&person.name, person.age)
+                        .map_err(|e| format!("{}: line {}: {}", file, line, e))?;
+                }
+                registry = imported;
+                println!("Imported {} people", people.len());
+            }
+            _ => return Err(format!("Unknown people action\n{}", USAGE)),
+        },
+    }
+    registry.save(&path)
+}
+
+fn print_people(records: &[&Record]) {
+    if records.is_empty() {
+        println!("Nobody matched");
+    }
+    for record in records {
+        println!("#{:<4} {:<24} {:>3}", record.id, record.person.name, record.person.age);
+    }
+}
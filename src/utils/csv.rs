@@ -0,0 +1,304 @@
+// Reading and writing CSV (comma-separated values), the format spreadsheets export.
+//
+// CSV looks simple, but fields can be wrapped in double quotes so they can contain
+// commas, new lines and quotes (written as two quotes: ""). This follows the rules
+// in RFC 4180, the document that describes the format.
+//
+//     name,city,quote
+//     Alice,"London, UK","She said ""hi"""
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Person;
+
+// Where something went wrong in the file. Both start at 1. The line is the line
+// in the text file where the row starts, which is what a text editor shows. It
+// isn't always the spreadsheet's row number, because blank lines are skipped and
+// a quoted field can go over several lines. The column counts fields, not chars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+fn error(line: usize, column: usize, message: &str) -> CsvError {
+    CsvError {
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+// Splits CSV text into rows of fields.
+// Both \r\n (what RFC 4180 asks for) and plain \n line endings are accepted.
+// Completely blank lines are skipped.
+#[allow(dead_code)]
+pub fn parse(text: &str) -> Result<Vec<Vec<String>>, CsvError> {
+    Ok(parse_with_lines(text)?.into_iter().map(|(_, row)| row).collect())
+}
+
+// Like parse(), but also gives the line each row starts on.
+fn parse_with_lines(text: &str) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let mut rows: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    // The line we are on, and the line the current row started on.
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut field = String::new();
+    // Whether the current field started with a quote, and whether we are still inside it.
+    let mut quoted = false;
+    let mut in_quotes = false;
+    // Set after a closing quote: only a comma or the end of the line may come next.
+    let mut after_quote = false;
+
+    // peekable() lets us look at the next character without using it up.
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let column_number = row.len() + 1;
+        // Nothing has been read for this row yet, so it starts here.
+        if row.is_empty() && field.is_empty() && !quoted {
+            row_line = line;
+        }
+
+        if in_quotes {
+            if c == '\n' {
+                line += 1;
+            }
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                    after_quote = true;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            ',' => {
+                row.push(std::mem::take(&mut field));
+                quoted = false;
+                after_quote = false;
+            }
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                // A line with nothing on it at all is skipped.
+                if !(row.is_empty() && field.is_empty() && !quoted) {
+                    row.push(std::mem::take(&mut field));
+                    rows.push((row_line, std::mem::take(&mut row)));
+                }
+                line += 1;
+                quoted = false;
+                after_quote = false;
+            }
+            _ if after_quote => {
+                return Err(error(
+                    line,
+                    column_number,
+                    &format!("unexpected '{}' after a closing quote", c),
+                ));
+            }
+            '"' if field.is_empty() && !quoted => {
+                quoted = true;
+                in_quotes = true;
+            }
+            '"' => {
+                return Err(error(
+                    line,
+                    column_number,
+                    "a quote can only appear inside a field that starts with a quote",
+                ));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(error(row_line, row.len() + 1, "the quoted field is never closed"));
+    }
+    // The last line doesn't need a line ending.
+    if !(row.is_empty() && field.is_empty() && !quoted) {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
+
+// Wraps a field in quotes if it needs them, doubling any quotes inside.
+fn quote_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Turns rows of fields back into CSV text, with \r\n line endings as RFC 4180 asks.
+pub fn write(rows: &[Vec<String>]) -> String {
+    let mut text = String::new();
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|f| quote_field(f)).collect();
+        text.push_str(&fields.join(","));
+        text.push_str("\r\n");
+    }
+    text
+}
+
+// A CSV file whose first row names the columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    // The line each row starts on (see CsvError), for pointing at it in errors.
+    pub lines: Vec<usize>,
+    header_line: usize,
+}
+
+impl CsvTable {
+    // Parses the text and checks every row has one field per header.
+    pub fn parse(text: &str) -> Result<CsvTable, CsvError> {
+        let mut rows = parse_with_lines(text)?;
+        if rows.is_empty() {
+            return Err(error(1, 1, "the file is empty, expected a header row"));
+        }
+
+        let (header_line, header_row) = rows.remove(0);
+        let headers: Vec<String> = header_row.iter().map(|h| h.trim().to_string()).collect();
+        for (i, header) in headers.iter().enumerate() {
+            if header.is_empty() {
+                return Err(error(header_line, i + 1, "header names can't be empty"));
+            }
+            if headers[..i].iter().any(|h| h.eq_ignore_ascii_case(header)) {
+                return Err(error(header_line, i + 1, &format!("the header '{}' appears twice", header)));
+            }
+        }
+
+        for (line, row) in &rows {
+            if row.len() != headers.len() {
+                return Err(error(
+                    *line,
+                    row.len().min(headers.len()) + 1,
+                    &format!("expected {} fields but found {}", headers.len(), row.len()),
+                ));
+            }
+        }
+
+        let (lines, rows) = rows.into_iter().unzip();
+        Ok(CsvTable { headers, rows, lines, header_line })
+    }
+
+    // Finds a column by its header, ignoring upper and lower case.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.headers.iter().position(|h| h.eq_ignore_ascii_case(name))
+    }
+
+    // Like column(), but an error (pointing at the header row) if it's missing.
+    pub fn require_column(&self, name: &str) -> Result<usize, CsvError> {
+        self.column(name)
+            .ok_or_else(|| error(self.header_line, self.headers.len() + 1, &format!("missing a '{}' column", name)))
+    }
+
+    // Reads a field and converts it with parse(), e.g. into an i32.
+    // On failure the error points at the line and column of the field.
+    pub fn parse_field<T: std::str::FromStr>(&self, row: usize, column: usize) -> Result<T, CsvError> {
+        let text = self.rows[row][column].trim();
+        text.parse::<T>().map_err(|_| {
+            error(
+                self.lines[row],
+                column + 1,
+                &format!("'{}' is not a valid {}", text, self.headers[column]),
+            )
+        })
+    }
+}
+
+// Loads a score table with "name" and "score" columns, like the scores HashMap in main.rs.
+pub fn load_scores(text: &str) -> Result<HashMap<String, i32>, CsvError> {
+    let table = CsvTable::parse(text)?;
+    let name_column = table.require_column("name")?;
+    let score_column = table.require_column("score")?;
+
+    let mut scores: HashMap<String, i32> = HashMap::new();
+    for (i, row) in table.rows.iter().enumerate() {
+        let name = row[name_column].trim();
+        if name.is_empty() {
+            return Err(error(table.lines[i], name_column + 1, "the name is empty"));
+        }
+        let score = table.parse_field(i, score_column)?;
+        if scores.insert(name.to_string(), score).is_some() {
+            return Err(error(table.lines[i], name_column + 1, &format!("{} appears more than once", name)));
+        }
+    }
+    Ok(scores)
+}
+
+// Loads a list of people from "name" and "age" columns.
+pub fn load_people(text: &str) -> Result<Vec<Person>, CsvError> {
+    Ok(load_people_with_lines(text)?.into_iter().map(|(_, person)| person).collect())
+}
+
+// Like load_people(), but also gives the line each person's row starts on, so
+// later checks can point at it too.
+pub fn load_people_with_lines(text: &str) -> Result<Vec<(usize, Person)>, CsvError> {
+    let table = CsvTable::parse(text)?;
+    let name_column = table.require_column("name")?;
+    let age_column = table.require_column("age")?;
+
+    let mut people: Vec<(usize, Person)> = Vec::new();
+    for (i, row) in table.rows.iter().enumerate() {
+        let person = Person {
+            name: row[name_column].trim().to_string(),
+            age: table.parse_field(i, age_column)?,
+        };
+        people.push((table.lines[i], person));
+    }
+    Ok(people)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_fields_and_blank_lines() {
+        let rows = parse("name,city\r\n\r\nAlice,\"London, UK\"\n\"Bob\",\"said \"\"hi\"\"\nthere\"").unwrap();
+        assert_eq!(rows, vec![
+            vec!["name", "city"],
+            vec!["Alice", "London, UK"],
+            vec!["Bob", "said \"hi\"\nthere"],
+        ]);
+        assert_eq!(parse(&write(&rows)).unwrap(), rows);
+    }
+
+    #[test]
+    fn errors_point_at_the_line_the_row_starts_on() {
+        // Bob's row is the 3rd row but starts on line 5, after a blank line and a
+        // field that goes over two lines.
+        let text = "name,age\nAlice,\"30\n\"\n\nBob,old\n";
+        let error = load_people(text).unwrap_err();
+        assert_eq!((error.line, error.column), (5, 2));
+
+        let table = CsvTable::parse("\n\nname,age\nAlice\n").unwrap_err();
+        assert_eq!((table.line, table.column), (4, 2));
+        let headers = CsvTable::parse("\nname,Name\n").unwrap_err();
+        assert_eq!((headers.line, headers.column), (2, 2));
+        let unclosed = parse("a,b\nc,\"d\ne\n").unwrap_err();
+        assert_eq!((unclosed.line, unclosed.message.as_str()), (2, "the quoted field is never closed"));
+        let stray = parse("a,b\n\"c\"\nd\"e\n").unwrap_err();
+        assert_eq!(stray.line, 3);
+    }
+}
@@ -0,0 +1,494 @@
+// Collections with a fixed capacity, somewhere between an array and a Vec.
+//
+// The arrays lesson in main.rs says an array like [&str; 3] can never grow,
+// while a Vec can grow forever. These types hold up to N items, where N is
+// part of the type just like the 3 in [&str; 3] (that's a "const generic"):
+//
+// - ArrayVec<T, N>: a list of up to N items. push() gives an error when it's full.
+// - RingBuffer<T, N>: keeps the last N items. When it's full, a push either
+//   overwrites the oldest item or is rejected, depending on the Overflow policy.
+// - SmallVec<T, N>: keeps up to N items in an ArrayVec, then moves them into a
+//   Vec ("spills to the heap") once there are more.
+//
+// The items are stored in an array of Options, with None in the empty slots,
+// so no unsafe code is needed.
+
+use std::array;
+use std::fmt;
+use std::iter::{Chain, Flatten};
+use std::slice;
+use std::vec;
+
+// Returned when there's no room for an item. The item is handed back so it isn't lost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapacityError<T> {
+    pub item: T,
+    pub capacity: usize,
+}
+
+impl<T: fmt::Debug> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no room for {:?}: the capacity is {}", self.item, self.capacity)
+    }
+}
+
+// What a full RingBuffer does with a new item.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    // Drop the oldest item to make room.
+    Overwrite,
+    // Keep the old items and return an error.
+    Reject,
+}
+
+// Goes through the items by reference. The empty slots are None, and flatten()
+// skips them, so only real items come out.
+pub struct Iter<'a, T> {
+    inner: Flatten<Chain<Slots<'a, T>, Slots<'a, T>>>,
+}
+
+// An iterator over the slots of the array, empty ones included.
+type Slots<'a, T> = slice::Iter<'a, Option<T>>;
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+}
+
+// Goes through the items by value, used by `for item in collection`.
+pub struct IntoIter<T, const N: usize> {
+    inner: Flatten<array::IntoIter<Option<T>, N>>,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct ArrayVec<T, const N: usize> {
+    // The first `len` slots are Some, the rest are None.
+    items: [Option<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    pub fn new() -> ArrayVec<T, N> {
+        ArrayVec {
+            // from_fn builds an array by calling the closure for each slot.
+            items: array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError { item, capacity: N });
+        }
+        self.items[self.len] = Some(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        // take() moves the value out and leaves None behind.
+        self.items[self.len].take()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.items.get_mut(index)?.as_mut()
+    }
+
+    // Removes the item at `index` and moves the later ones down, like Vec::remove.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let item = self.items[index].take();
+        // rotate_left(1) moves the empty slot to the end of the used part.
+        self.items[index..self.len].rotate_left(1);
+        self.len -= 1;
+        item
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        // The chain adds an empty slice. It's only there because RingBuffer needs
+        // two parts, and this way both types can share the same Iter.
+        Iter {
+            inner: self.items.iter().chain(self.items[..0].iter()).flatten(),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> Self {
+        ArrayVec::new()
+    }
+}
+
+// Prints like a Vec, [1, 2, 3], without the empty slots.
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for ArrayVec<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        IntoIter {
+            inner: self.items.into_iter().flatten(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RingBuffer<T, const N: usize> {
+    items: [Option<T>; N],
+    // The slot holding the oldest item. The others follow it, wrapping round to slot 0.
+    start: usize,
+    len: usize,
+    overflow: Overflow,
+}
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    pub fn new(overflow: Overflow) -> RingBuffer<T, N> {
+        RingBuffer {
+            items: array::from_fn(|_| None),
+            start: 0,
+            len: 0,
+            overflow,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    // The slot for the item that is `offset` places after the oldest one.
+    fn slot(&self, offset: usize) -> usize {
+        (self.start + offset) % N
+    }
+
+    // Adds an item as the newest. With Overflow::Overwrite a full buffer drops
+    // its oldest item, which is returned as Ok(Some(oldest)).
+    pub fn push(&mut self, item: T) -> Result<Option<T>, CapacityError<T>> {
+        if !self.is_full() {
+            let slot = self.slot(self.len);
+            self.items[slot] = Some(item);
+            self.len += 1;
+            return Ok(None);
+        }
+        match self.overflow {
+            Overflow::Reject => Err(CapacityError { item, capacity: N }),
+            // A buffer with no slots at all can't keep anything, so the new item is what gets dropped.
+            Overflow::Overwrite if N == 0 => Ok(Some(item)),
+            Overflow::Overwrite => {
+                // The oldest slot becomes the newest, and the next one along is now the oldest.
+                let oldest = self.items[self.start].replace(item);
+                self.start = self.slot(1);
+                Ok(oldest)
+            }
+        }
+    }
+
+    // Removes and returns the oldest item.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let item = self.items[self.start].take();
+        self.start = self.slot(1);
+        self.len -= 1;
+        item
+    }
+
+    // Removes and returns the newest item.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        let slot = self.slot(self.len);
+        self.items[slot].take()
+    }
+
+    // 0 is the oldest item.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        self.items[self.slot(index)].as_ref()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+        self.start = 0;
+    }
+
+    // Oldest first. The items from `start` to the end of the array come first,
+    // then the ones that wrapped round to the beginning.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (wrapped, first) = self.items.split_at(self.start);
+        Iter {
+            inner: first.iter().chain(wrapped.iter()).flatten(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for RingBuffer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a RingBuffer<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for RingBuffer<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(mut self) -> IntoIter<T, N> {
+        // Turning the array round so the oldest item is in slot 0 puts everything in order.
+        self.items.rotate_left(self.start);
+        IntoIter {
+            inner: self.items.into_iter().flatten(),
+        }
+    }
+}
+
+// Where a SmallVec keeps its items.
+#[derive(Clone, PartialEq)]
+enum Storage<T, const N: usize> {
+    Inline(ArrayVec<T, N>),
+    Heap(Vec<T>),
+}
+
+#[derive(Clone, PartialEq)]
+pub struct SmallVec<T, const N: usize> {
+    storage: Storage<T, N>,
+}
+
+impl<T, const N: usize> SmallVec<T, N> {
+    pub fn new() -> SmallVec<T, N> {
+        SmallVec {
+            storage: Storage::Inline(ArrayVec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(items) => items.len(),
+            Storage::Heap(items) => items.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // True once the items have moved into a Vec. They stay there even if some are removed.
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.storage, Storage::Heap(_))
+    }
+
+    // Always works: if the inline space is full, everything moves into a Vec first.
+    pub fn push(&mut self, item: T) {
+        match &mut self.storage {
+            Storage::Heap(items) => items.push(item),
+            Storage::Inline(items) => {
+                if let Err(full) = items.push(item) {
+                    // take() leaves an empty ArrayVec behind so we can own the old one.
+                    let mut spilled: Vec<T> = std::mem::take(items).into_iter().collect();
+                    spilled.push(full.item);
+                    self.storage = Storage::Heap(spilled);
+                }
+            }
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline(items) => items.pop(),
+            Storage::Heap(items) => items.pop(),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        match &self.storage {
+            Storage::Inline(items) => items.get(index),
+            Storage::Heap(items) => items.get(index),
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline(items) => items.remove(index),
+            Storage::Heap(items) if index < items.len() => Some(items.remove(index)),
+            Storage::Heap(_) => None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match &mut self.storage {
+            Storage::Inline(items) => items.clear(),
+            Storage::Heap(items) => items.clear(),
+        }
+    }
+
+    pub fn iter(&self) -> SmallIter<'_, T> {
+        match &self.storage {
+            Storage::Inline(items) => SmallIter::Inline(items.iter()),
+            Storage::Heap(items) => SmallIter::Heap(items.iter()),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SmallVec<T, N> {
+    fn default() -> Self {
+        SmallVec::new()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SmallVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// A SmallVec's iterator is one of two kinds, depending on where the items are.
+pub enum SmallIter<'a, T> {
+    Inline(Iter<'a, T>),
+    Heap(slice::Iter<'a, T>),
+}
+
+impl<'a, T> Iterator for SmallIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self {
+            SmallIter::Inline(items) => items.next(),
+            SmallIter::Heap(items) => items.next(),
+        }
+    }
+}
+
+pub enum SmallIntoIter<T, const N: usize> {
+    Inline(IntoIter<T, N>),
+    Heap(vec::IntoIter<T>),
+}
+
+impl<T, const N: usize> Iterator for SmallIntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self {
+            SmallIntoIter::Inline(items) => items.next(),
+            SmallIntoIter::Heap(items) => items.next(),
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SmallVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = SmallIter<'a, T>;
+
+    fn into_iter(self) -> SmallIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for SmallVec<T, N> {
+    type Item = T;
+    type IntoIter = SmallIntoIter<T, N>;
+
+    fn into_iter(self) -> SmallIntoIter<T, N> {
+        match self.storage {
+            Storage::Inline(items) => SmallIntoIter::Inline(items.into_iter()),
+            Storage::Heap(items) => SmallIntoIter::Heap(items.into_iter()),
+        }
+    }
+}
+
+// collect() works for SmallVec, since push() never fails.
+impl<T, const N: usize> FromIterator<T> for SmallVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut small = SmallVec::new();
+        for item in iter {
+            small.push(item);
+        }
+        small
+    }
+}
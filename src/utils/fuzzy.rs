@@ -0,0 +1,179 @@
+// Fuzzy string matching: how similar are two strings, and what did the user mean?
+//
+// scores.get("Alcie") just returns None, even though "Alice" is obviously what
+// was meant. The distance functions below measure how different two strings
+// are, and did_you_mean() uses them to pick the closest key.
+//
+// - Levenshtein distance: the number of single-char insertions, deletions or
+//   substitutions to turn one string into the other. "Alcie" => "Alice" is 2.
+// - Damerau-Levenshtein distance: the same, but swapping two neighbouring chars
+//   counts as one edit, so "Alcie" => "Alice" is 1. Typos are often swaps.
+// - Jaro-Winkler similarity: a score from 0.0 (nothing alike) to 1.0 (identical)
+//   that rewards strings which start the same way. Good for short names.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // previous[j] is the distance between the first i-1 chars of a and the first j chars of b.
+    // We only ever need the previous row, so we don't keep the whole table.
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            current[j] = (previous[j] + 1) // delete from a
+                .min(current[j - 1] + 1) // insert into a
+                .min(previous[j - 1] + cost); // substitute
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// The full (unrestricted) Damerau-Levenshtein distance, which also allows a
+// swapped pair to be edited again afterwards.
+pub fn damerau_levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max = a.len() + b.len();
+
+    // The table has an extra row and column at the start filled with `max`,
+    // which acts as "infinity" so transpositions can't reach off the edge.
+    let mut d = vec![vec![0; b.len() + 2]; a.len() + 2];
+    d[0][0] = max;
+    for i in 0..=a.len() {
+        d[i + 1][0] = max;
+        d[i + 1][1] = i;
+    }
+    for j in 0..=b.len() {
+        d[0][j + 1] = max;
+        d[1][j + 1] = j;
+    }
+
+    // The last row each char of a was seen in.
+    let mut last_row: HashMap<char, usize> = HashMap::new();
+    for i in 1..=a.len() {
+        let mut last_match_column = 0;
+        for j in 1..=b.len() {
+            let k = last_row.get(&b[j - 1]).copied().unwrap_or(0);
+            let l = last_match_column;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_match_column = j;
+                0
+            } else {
+                1
+            };
+            d[i + 1][j + 1] = (d[i][j] + cost)
+                .min(d[i + 1][j] + 1)
+                .min(d[i][j + 1] + 1)
+                .min(d[k][l] + (i - k - 1) + 1 + (j - l - 1));
+        }
+        last_row.insert(a[i - 1], i);
+    }
+    d[a.len() + 1][b.len() + 1]
+}
+
+pub fn jaro(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    // Two chars "match" if they're the same and not too far apart.
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0;
+    for i in 0..a.len() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        for j in start..end {
+            if !b_matched[j] && a[i] == b[j] {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    // Transpositions: matched chars that appear in a different order.
+    let a_order = a.iter().zip(&a_matched).filter(|(_, m)| **m).map(|(c, _)| c);
+    let b_order = b.iter().zip(&b_matched).filter(|(_, m)| **m).map(|(c, _)| c);
+    let transpositions = a_order.zip(b_order).filter(|(x, y)| x != y).count() / 2;
+
+    let m = matches as f64;
+    (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0
+}
+
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let similarity = jaro(a, b);
+    // Strings that share the same first few chars (up to 4) get a bonus.
+    let prefix = a.chars().zip(b.chars()).take(4).take_while(|(x, y)| x == y).count();
+    similarity + prefix as f64 * 0.1 * (1.0 - similarity)
+}
+
+// Up to `limit` keys that look like `input`, best first. Case is ignored.
+// A key counts as close if it is within a few edits (about one per three chars)
+// or its Jaro-Winkler similarity is high.
+pub fn suggestions<'a, I, S>(input: &str, keys: I, limit: usize) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a S>,
+    S: AsRef<str> + ?Sized + 'a,
+{
+    let input = input.to_lowercase();
+    let max_distance = (input.chars().count() / 3).max(1);
+
+    let mut scored: Vec<(usize, f64, &str)> = keys
+        .into_iter()
+        .map(|key| {
+            let key = key.as_ref();
+            let lower = key.to_lowercase();
+            (damerau_levenshtein(&input, &lower), jaro_winkler(&input, &lower), key)
+        })
+        .filter(|(distance, similarity, _)| *distance <= max_distance || *similarity >= 0.9)
+        .collect();
+
+    // Fewest edits first, then the most similar, then alphabetical so ties are predictable.
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)).then(a.2.cmp(b.2)));
+    scored.dedup_by(|a, b| a.2 == b.2);
+    scored.into_iter().take(limit).map(|(_, _, key)| key).collect()
+}
+
+// The single closest key, if any are close enough.
+pub fn did_you_mean<'a, I, S>(input: &str, keys: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a S>,
+    S: AsRef<str> + ?Sized + 'a,
+{
+    suggestions(input, keys, 1).into_iter().next()
+}
+
+// Looks up a key in a HashMap, and if it isn't there, returns an error that
+// suggests the closest key. Works for HashMap<&str, _> and HashMap<String, _>.
+//
+//     get_or_suggest(&scores, "Alcie") => Err("No entry for 'Alcie'. Did you mean 'Alice'?")
+pub fn get_or_suggest<'a, K, V>(map: &'a HashMap<K, V>, key: &str) -> Result<&'a V, String>
+where
+    K: Borrow<str> + AsRef<str> + Hash + Eq,
+{
+    if let Some(value) = map.get(key) {
+        return Ok(value);
+    }
+    match did_you_mean(key, map.keys()) {
+        Some(suggestion) => Err(format!("No entry for '{}'. Did you mean '{}'?", key, suggestion)),
+        None => Err(format!("No entry for '{}'", key)),
+    }
+}
@@ -0,0 +1,469 @@
+// A gradebook: students, weighted assignments and the scores in between.
+//
+// The HashMap lesson in main.rs keeps one score per student in a
+// HashMap<&str, i32> that disappears when the program ends. Here each student
+// has a HashMap of assignment name => score, assignments have weights, and
+// the whole thing can be saved to and loaded from a text file.
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::utils::csv::{self, CsvTable};
+use crate::utils::validation::{self, Validate, Validator, Violation};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub name: String,
+    // How much this assignment counts towards the average, e.g. 0.25 for 25%.
+    // Weights don't have to add up to 1; they are compared with each other.
+    pub weight: f64,
+    // The score that counts as full marks, e.g. 20 for a quiz marked out of 20.
+    pub max_score: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gradebook {
+    // Vecs keep the order things were added in, so reports come out the same every time.
+    pub students: Vec<String>,
+    pub assignments: Vec<Assignment>,
+    // student name => (assignment name => score)
+    pub scores: HashMap<String, HashMap<String, f64>>,
+}
+
+// Summary statistics for a list of numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassStats {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    // Population standard deviation: how far scores are from the mean, on average.
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+// Ways to curve the class averages.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Curve {
+    // Add the same number of points to everyone.
+    Add(f64),
+    // Scale everyone up so the best average becomes 100.
+    TopToHundred,
+    // Shift everyone so the class mean becomes this value.
+    TargetMean(f64),
+}
+
+// Checks a name can be saved in the file, which uses tabs and new lines to separate things.
+fn check_name(kind: &str, name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err(format!("The {} name can't be empty", kind));
+    }
+    name_rule(name).map_err(|e| format!("The {} name {}", kind, e))
+}
+
+// Names are saved one per line with tabs between the fields, so they can't have either in them.
+// The validators below use this too.
+fn name_rule(name: &str) -> Result<(), String> {
+    if name.contains('\t') || name.contains('\n') {
+        return Err(String::from("can't contain tabs or new lines"));
+    }
+    Ok(())
+}
+
+// above(0.0) lets infinity through, and an infinite weight turns every average into NaN.
+fn finite_rule(number: f64) -> Result<(), String> {
+    if !number.is_finite() {
+        return Err(format!("must be a finite number, not {}", number));
+    }
+    Ok(())
+}
+
+impl Validate for Assignment {
+    fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut v = Validator::new();
+        v.text("name", &self.name).not_empty().custom(name_rule);
+        v.number("weight", self.weight).above(0.0).custom(finite_rule);
+        v.number("max_score", self.max_score).above(0.0).custom(finite_rule);
+        v.finish()
+    }
+}
+
+// add_student(), add_assignment() and set_score() already refuse bad values,
+// but the fields are pub, so a Gradebook built or changed by hand can still
+// end up with a NaN weight or a score for a student who isn't there. This
+// finds every problem like that at once.
+impl Validate for Gradebook {
+    fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut v = Validator::new();
+        for (i, student) in self.students.iter().enumerate() {
+            let path = format!("students[{}]", i);
+            v.text(&path, student).not_empty().custom(name_rule);
+            v.check(&path, !self.students[..i].contains(student), "is in the gradebook twice");
+        }
+        v.each("assignments", &self.assignments);
+        for (i, a) in self.assignments.iter().enumerate() {
+            let used_before = self.assignments[..i].iter().any(|other| other.name == a.name);
+            v.check(&format!("assignments[{}].name", i), !used_before, "is used by another assignment");
+        }
+
+        // A HashMap has no order, so sort the names to report problems the same way every time.
+        let mut students: Vec<&String> = self.scores.keys().collect();
+        students.sort();
+        for student in students {
+            let mut assignments: Vec<(&String, &f64)> = self.scores[student].iter().collect();
+            assignments.sort_by(|a, b| a.0.cmp(b.0));
+            v.check(
+                &format!("scores[{:?}]", student),
+                self.students.contains(student),
+                "isn't a student in the gradebook",
+            );
+            for (name, &score) in assignments {
+                let path = format!("scores[{:?}][{:?}]", student, name);
+                match self.assignment(name) {
+                    Some(a) => {
+                        v.number(&path, score).range(0.0..=a.max_score);
+                    }
+                    None => v.add(&path, "isn't an assignment in the gradebook"),
+                }
+            }
+        }
+        v.finish()
+    }
+}
+
+impl Gradebook {
+    pub fn new() -> Gradebook {
+        Gradebook::default()
+    }
+
+    pub fn add_student(&mut self, name: &str) -> Result<(), String> {
+        check_name("student", name)?;
+        if self.students.iter().any(|s| s == name) {
+            return Err(format!("{} is already in the gradebook", name));
+        }
+        self.students.push(name.to_string());
+        Ok(())
+    }
+
+    // Removes a student and all of their scores.
+    pub fn remove_student(&mut self, name: &str) -> Result<(), String> {
+        let index = self.student_index(name)?;
+        self.students.remove(index);
+        self.scores.remove(name);
+        Ok(())
+    }
+
+    pub fn add_assignment(&mut self, name: &str, weight: f64, max_score: f64) -> Result<(), String> {
+        check_name("assignment", name)?;
+        if self.assignment(name).is_some() {
+            return Err(format!("There is already an assignment called {}", name));
+        }
+        if !weight.is_finite() || weight <= 0.0 {
+            return Err(format!("The weight must be a number above 0, not {}", weight));
+        }
+        if !max_score.is_finite() || max_score <= 0.0 {
+            return Err(format!("The maximum score must be a number above 0, not {}", max_score));
+        }
+        self.assignments.push(Assignment {
+            name: name.to_string(),
+            weight,
+            max_score,
+        });
+        Ok(())
+    }
+
+    // Removes an assignment and everyone's score for it.
+    pub fn remove_assignment(&mut self, name: &str) -> Result<(), String> {
+        let index = self
+            .assignments
+            .iter()
+            .position(|a| a.name == name)
+            .ok_or_else(|| format!("There is no assignment called {}", name))?;
+        self.assignments.remove(index);
+        for student_scores in self.scores.values_mut() {
+            student_scores.remove(name);
+        }
+        Ok(())
+    }
+
+    pub fn assignment(&self, name: &str) -> Option<&Assignment> {
+        self.assignments.iter().find(|a| a.name == name)
+    }
+
+    fn student_index(&self, name: &str) -> Result<usize, String> {
+        self.students
+            .iter()
+            .position(|s| s == name)
+            .ok_or_else(|| format!("{} is not in the gradebook", name))
+    }
+
+    // Adds or replaces a score, just like scores.insert() in the HashMap lesson.
+    pub fn set_score(&mut self, student: &str, assignment: &str, score: f64) -> Result<(), String> {
+        self.student_index(student)?;
+        let max = self
+            .assignment(assignment)
+            .ok_or_else(|| format!("There is no assignment called {}", assignment))?
+            .max_score;
+        if score.is_nan() || score < 0.0 || score > max {
+            return Err(format!("{} is outside 0 to {} for {}", score, max, assignment));
+        }
+
+        // entry() gets the student's inner HashMap, creating an empty one the first time.
+        self.scores
+            .entry(student.to_string())
+            .or_default()
+            .insert(assignment.to_string(), score);
+        Ok(())
+    }
+
+    pub fn remove_score(&mut self, student: &str, assignment: &str) -> Result<(), String> {
+        self.scores
+            .get_mut(student)
+            .and_then(|s| s.remove(assignment))
+            .map(|_| ())
+            .ok_or_else(|| format!("{} has no score for {}", student, assignment))
+    }
+
+    pub fn score(&self, student: &str, assignment: &str) -> Option<f64> {
+        self.scores.get(student)?.get(assignment).copied()
+    }
+
+    // The student's weighted average as a percentage.
+    // Assignments they have no score for are left out rather than counted as zero.
+    // Returns None if they have no scores at all.
+    pub fn weighted_average(&self, student: &str) -> Option<f64> {
+        let mut total = 0.0;
+        let mut total_weight = 0.0;
+        for assignment in &self.assignments {
+            if let Some(score) = self.score(student, &assignment.name) {
+                total += assignment.weight * score / assignment.max_score * 100.0;
+                total_weight += assignment.weight;
+            }
+        }
+        if total_weight == 0.0 {
+            None
+        } else {
+            Some(total / total_weight)
+        }
+    }
+
+    // Every student's average, in the order they were added. Students without scores are skipped.
+    pub fn averages(&self) -> Vec<(String, f64)> {
+        self.students
+            .iter()
+            .filter_map(|s| self.weighted_average(s).map(|avg| (s.clone(), avg)))
+            .collect()
+    }
+
+    // Students from best to worst average, with their position.
+    // Students with the same average share a position, and the next one is skipped (1, 2, 2, 4).
+    pub fn ranking(&self) -> Vec<(usize, String, f64)> {
+        let mut averages = self.averages();
+        averages.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut ranked: Vec<(usize, String, f64)> = Vec::new();
+        for (i, (name, average)) in averages.into_iter().enumerate() {
+            let rank = match ranked.last() {
+                Some((previous_rank, _, previous)) if *previous == average => *previous_rank,
+                _ => i + 1,
+            };
+            ranked.push((rank, name, average));
+        }
+        ranked
+    }
+
+    // Statistics for the students' averages, or None if nobody has any scores.
+    pub fn class_stats(&self) -> Option<ClassStats> {
+        let values: Vec<f64> = self.averages().into_iter().map(|(_, avg)| avg).collect();
+        ClassStats::from_values(&values)
+    }
+
+    // The class averages after applying a curve. Averages are capped at 100 and
+    // the stored scores are left alone, so you can try different curves.
+    pub fn curved_averages(&self, curve: &Curve) -> Vec<(String, f64)> {
+        let averages = self.averages();
+        let adjust: Box<dyn Fn(f64) -> f64> = match curve {
+            Curve::Add(points) => {
+                let points = *points;
+                Box::new(move |avg| avg + points)
+            }
+            Curve::TopToHundred => {
+                let top = averages.iter().map(|(_, avg)| *avg).fold(0.0, f64::max);
+                Box::new(move |avg| if top > 0.0 { avg * 100.0 / top } else { avg })
+            }
+            Curve::TargetMean(target) => {
+                let values: Vec<f64> = averages.iter().map(|(_, avg)| *avg).collect();
+                let shift = ClassStats::from_values(&values).map_or(0.0, |s| target - s.mean);
+                Box::new(move |avg| avg + shift)
+            }
+        };
+
+        averages
+            .iter()
+            .map(|(name, avg)| (name.clone(), adjust(*avg).clamp(0.0, 100.0)))
+            .collect()
+    }
+
+    // Saves the gradebook as lines of tab-separated values, one thing per line:
+    //
+    //     assignment  Homework 1  0.25  100
+    //     student     Alice
+    //     score       Alice       Homework 1  90
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut text = String::new();
+        for a in &self.assignments {
+            text.push_str(&format!("assignment\t{}\t{}\t{}\n", a.name, a.weight, a.max_score));
+        }
+        for student in &self.students {
+            text.push_str(&format!("student\t{}\n", student));
+        }
+        for student in &self.students {
+            for a in &self.assignments {
+                if let Some(score) = self.score(student, &a.name) {
+                    text.push_str(&format!("score\t{}\t{}\t{}\n", student, a.name, score));
+                }
+            }
+        }
+        fs::write(path, text).map_err(|e| format!("Could not write {}: {}", path, e))
+    }
+
+    // Loads a gradebook saved by save(). A missing file gives an empty gradebook,
+    // so the first command you run creates it.
+    pub fn load(path: &str) -> Result<Gradebook, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Gradebook::new()),
+            Err(e) => return Err(format!("Could not read {}: {}", path, e)),
+        };
+
+        let mut book = Gradebook::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let number = |i: usize| -> Result<f64, String> {
+                fields[i]
+                    .parse::<f64>()
+                    .map_err(|_| format!("'{}' is not a number", fields[i]))
+            };
+            let result = match (fields[0], fields.len()) {
+                ("assignment", 4) => book.add_assignment(fields[1], number(2)?, number(3)?),
+                ("student", 2) => book.add_student(fields[1]),
+                ("score", 4) => book.set_score(fields[1], fields[2], number(3)?),
+                _ => Err(format!("unrecognised line '{}'", line)),
+            };
+            result.map_err(|e| format!("{} line {}: {}", path, index + 1, e))?;
+        }
+        // Each line was checked as it was added; this checks the gradebook as a whole.
+        book.validate().map_err(|v| format!("{}: {}", path, validation::describe(&v)))?;
+        Ok(book)
+    }
+
+    // Writes a score table that opens in a spreadsheet: one row per student,
+    // one column per assignment, and their average at the end.
+    pub fn export_csv(&self) -> String {
+        let mut header = vec![String::from("student")];
+        header.extend(self.assignments.iter().map(|a| a.name.clone()));
+        header.push(String::from("average"));
+
+        let mut rows = vec![header];
+        for student in &self.students {
+            let mut row = vec![student.clone()];
+            for a in &self.assignments {
+                row.push(self.score(student, &a.name).map(|s| s.to_string()).unwrap_or_default());
+            }
+            row.push(
+                self.weighted_average(student)
+                    .map(|avg| format!("{:.2}", avg))
+                    .unwrap_or_default(),
+            );
+            rows.push(row);
+        }
+        csv::write(&rows)
+    }
+
+    // Reads a score table in the same layout as export_csv. Students and assignments
+    // that don't exist yet are added (new assignments get a weight of 1, out of 100).
+    // Empty cells and an "average" column are ignored. Returns how many scores were set.
+    pub fn import_csv(&mut self, text: &str) -> Result<usize, String> {
+        let table = CsvTable::parse(text).map_err(|e| e.to_string())?;
+        let student_column = table.require_column("student").map_err(|e| e.to_string())?;
+
+        let mut count = 0;
+        for (i, row) in table.rows.iter().enumerate() {
+            let student = row[student_column].trim();
+            if !self.students.iter().any(|s| s == student) {
+                self.add_student(student).map_err(|e| format!("line {}: {}", table.lines[i], e))?;
+            }
+            for (column, header) in table.headers.iter().enumerate() {
+                if column == student_column || header.eq_ignore_ascii_case("average") || row[column].trim().is_empty() {
+                    continue;
+                }
+                if self.assignment(header).is_none() {
+                    self.add_assignment(header, 1.0, 100.0)?;
+                }
+                let score: f64 = table.parse_field(i, column).map_err(|e| e.to_string())?;
+                self.set_score(student, header, score)
+                    .map_err(|e| format!("line {}, column {}: {}", table.lines[i], column + 1, e))?;
+                count += 1;
+            }
+        }
+        self.validate().map_err(|v| validation::describe(&v))?;
+        Ok(count)
+    }
+}
+
+impl ClassStats {
+    pub fn from_values(values: &[f64]) -> Option<ClassStats> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+
+        Some(ClassStats {
+            count,
+            mean,
+            median: percentile(&sorted, 50.0),
+            std_dev: variance.sqrt(),
+            min: sorted[0],
+            max: sorted[count - 1],
+        })
+    }
+}
+
+// The value below which p percent of the numbers fall, e.g. p = 90 for the 90th percentile.
+// The numbers must already be sorted. Between two numbers we draw a straight line
+// (linear interpolation), so the 50th percentile of [1, 2, 3, 4] is 2.5.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_and_max_scores_must_be_finite() {
+        let mut book = Gradebook::new();
+        for bad in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(book.add_assignment("Quiz", bad, 10.0).unwrap_err().starts_with("The weight"));
+            assert!(book.add_assignment("Quiz", 1.0, bad).unwrap_err().starts_with("The maximum score"));
+        }
+        assert!(book.add_assignment("Quiz", 1.0, 10.0).is_ok());
+
+        let loaded = Assignment { name: String::from("Essay"), weight: f64::INFINITY, max_score: f64::INFINITY };
+        assert_eq!(loaded.validate().unwrap_err().len(), 2);
+    }
+}
@@ -0,0 +1,437 @@
+// A configurable grading scale.
+//
+// The control flow lesson in main.rs hardcodes the grades with if/else:
+// 90 and up is an A, 80 is a B, 70 is a C, and everything else is an F.
+// A GradingScale keeps the letter bands in a Vec instead, so they can be
+// loaded from a file, checked for mistakes, and given + and - modifiers.
+
+use std::fmt;
+use std::fs;
+
+use crate::utils::json::{self, FromJson, JsonValue};
+
+// One letter grade and the range of scores that earn it.
+// min is inclusive and max is exclusive, except for the top band,
+// which also includes the maximum score (so 100 is still an A).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Band {
+    pub letter: String,
+    pub min: f64,
+    pub max: f64,
+    // Whether this band can get a + or -. Most scales don't have an F+.
+    pub modifiers: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradingScale {
+    pub bands: Vec<Band>,
+    pub max_score: f64,
+    // Scores at or above this pass, everything below fails.
+    pub pass_mark: f64,
+    pub plus_minus: bool,
+    // How close to the top or bottom of a band a score must be to get a + or -.
+    pub modifier_width: f64,
+}
+
+// The result of grading a score, e.g. "B+" that passed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grade {
+    pub letter: String,
+    pub modifier: Option<char>,
+    pub passed: bool,
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.letter)?;
+        if let Some(modifier) = self.modifier {
+            write!(f, "{}", modifier)?;
+        }
+        write!(f, " ({})", if self.passed { "pass" } else { "fail" })
+    }
+}
+
+impl Band {
+    pub fn new(letter: &str, min: f64, max: f64) -> Band {
+        Band {
+            letter: letter.to_string(),
+            min,
+            max,
+            modifiers: true,
+        }
+    }
+}
+
+// The default scale is the if/else ladder from main.rs, plus the missing D.
+impl Default for GradingScale {
+    fn default() -> Self {
+        let mut fail = Band::new("F", 0.0, 60.0);
+        fail.modifiers = false;
+
+        GradingScale {
+            bands: vec![
+                Band::new("A", 90.0, 100.0),
+                Band::new("B", 80.0, 90.0),
+                Band::new("C", 70.0, 80.0),
+                Band::new("D", 60.0, 70.0),
+                fail,
+            ],
+            max_score: 100.0,
+            pass_mark: 60.0,
+            plus_minus: false,
+            modifier_width: 3.0,
+        }
+    }
+}
+
+impl GradingScale {
+    // Checks the scale makes sense. The bands must start at 0, end at max_score,
+    // and each band must start exactly where the one below it stops, so there
+    // are no gaps (scores with no grade) or overlaps (scores with two grades).
+    pub fn validate(&self) -> Result<(), String> {
+        if self.bands.is_empty() {
+            return Err(String::from("The scale has no bands"));
+        }
+        // NaN fails every comparison, so it would slip past the checks below.
+        // Infinity can't be a real score either.
+        let numbers = [
+            ("max_score", self.max_score),
+            ("pass_mark", self.pass_mark),
+            ("modifier_width", self.modifier_width),
+        ];
+        for (name, number) in numbers {
+            if !number.is_finite() {
+                return Err(format!("{} must be a normal number, not {}", name, number));
+            }
+        }
+        if self.max_score <= 0.0 {
+            return Err(format!("max_score must be above 0, not {}", self.max_score));
+        }
+        if self.pass_mark < 0.0 || self.pass_mark > self.max_score {
+            return Err(format!(
+                "pass_mark {} is outside 0 to {}",
+                self.pass_mark, self.max_score
+            ));
+        }
+        if self.modifier_width < 0.0 {
+            return Err(String::from("modifier_width can't be negative"));
+        }
+
+        let mut letters: Vec<&str> = Vec::new();
+        for band in &self.bands {
+            if band.letter.trim().is_empty() {
+                return Err(String::from("Every band needs a letter"));
+            }
+            if letters.contains(&band.letter.as_str()) {
+                return Err(format!("The letter {} is used by more than one band", band.letter));
+            }
+            letters.push(&band.letter);
+            if !band.min.is_finite() || !band.max.is_finite() {
+                return Err(format!(
+                    "Band {} must start and end at normal numbers, not {} and {}",
+                    band.letter, band.min, band.max
+                ));
+            }
+            if band.min >= band.max {
+                return Err(format!(
+                    "Band {} starts at {} but ends at {}",
+                    band.letter, band.min, band.max
+                ));
+            }
+        }
+
+        let sorted = self.sorted_bands();
+        if sorted[0].min != 0.0 {
+            return Err(format!(
+                "The lowest band ({}) must start at 0, not {}",
+                sorted[0].letter, sorted[0].min
+            ));
+        }
+        for pair in sorted.windows(2) {
+            let (lower, upper) = (pair[0], pair[1]);
+            if upper.min > lower.max {
+                return Err(format!(
+                    "Gap between {} (ends at {}) and {} (starts at {})",
+                    lower.letter, lower.max, upper.letter, upper.min
+                ));
+            }
+            if upper.min < lower.max {
+                return Err(format!(
+                    "{} (ends at {}) overlaps {} (starts at {})",
+                    lower.letter, lower.max, upper.letter, upper.min
+                ));
+            }
+        }
+        let top = sorted[sorted.len() - 1];
+        if top.max != self.max_score {
+            return Err(format!(
+                "The top band ({}) must end at max_score {}, not {}",
+                top.letter, self.max_score, top.max
+            ));
+        }
+
+        Ok(())
+    }
+
+    // The bands from lowest to highest score.
+    fn sorted_bands(&self) -> Vec<&Band> {
+        let mut sorted: Vec<&Band> = self.bands.iter().collect();
+        sorted.sort_by(|a, b| a.min.total_cmp(&b.min));
+        sorted
+    }
+
+    // Works out the grade for a score. This is the if/else ladder from main.rs,
+    // but it loops over the bands instead of having one branch per letter.
+    pub fn grade(&self, score: f64) -> Result<Grade, String> {
+        if score.is_nan() || score < 0.0 || score > self.max_score {
+            return Err(format!("Score {} is outside 0 to {}", score, self.max_score));
+        }
+
+        let band = self
+            .bands
+            .iter()
+            .find(|b| score >= b.min && (score < b.max || (score == self.max_score && b.max == score)))
+            .ok_or_else(|| format!("No band covers the score {}", score))?;
+
+        let mut modifier = None;
+        if self.plus_minus && band.modifiers {
+            // The top band includes max_score itself, so a perfect score gets a + too.
+            if score >= band.max - self.modifier_width {
+                modifier = Some('+');
+            } else if score < band.min + self.modifier_width {
+                modifier = Some('-');
+            }
+        }
+
+        Ok(Grade {
+            letter: band.letter.clone(),
+            modifier,
+            passed: score >= self.pass_mark,
+        })
+    }
+
+    // Loads a scale from a TOML file, or a JSON file if the name ends in .json.
+    // See from_toml and the FromJson impl below for the formats.
+    pub fn load(path: &str) -> Result<GradingScale, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+        if path.ends_with(".json") {
+            let value = json::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+            GradingScale::from_json(&value).map_err(|e| format!("{}: {}", path, e))
+        } else {
+            GradingScale::from_toml(&text)
+        }
+    }
+
+    // Reads a scale written in (a small part of) TOML, for example:
+    //
+    //     max_score = 100
+    //     pass_mark = 50
+    //     plus_minus = true
+    //
+    //     [[band]]
+    //     letter = "Pass"
+    //     min = 50
+    //     max = 100
+    //
+    //     [[band]]
+    //     letter = "Fail"
+    //     min = 0
+    //     max = 50
+    //     modifiers = false
+    //
+    // Anything you leave out keeps the value from the default scale.
+    pub fn from_toml(text: &str) -> Result<GradingScale, String> {
+        let mut scale = GradingScale::default();
+        let mut bands: Vec<Band> = Vec::new();
+        // Which band the key = value lines belong to. None means the top of the file.
+        let mut current: Option<usize> = None;
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line == "[[band]]" {
+                bands.push(Band::new("", 0.0, 0.0));
+                current = Some(bands.len() - 1);
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(format!("Line {}: unknown table {}", line_number, line));
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Line {}: expected key = value", line_number))?;
+            let key = key.trim();
+            let value = TomlValue::parse(value.trim())
+                .map_err(|e| format!("Line {}: {}", line_number, e))?;
+
+            let result = match current {
+                None => match key {
+                    "max_score" => value.number().map(|n| scale.max_score = n),
+                    "pass_mark" => value.number().map(|n| scale.pass_mark = n),
+                    "plus_minus" => value.boolean().map(|b| scale.plus_minus = b),
+                    "modifier_width" => value.number().map(|n| scale.modifier_width = n),
+                    _ => Err(format!("unknown setting '{}'", key)),
+                },
+                Some(i) => {
+                    let band = &mut bands[i];
+                    match key {
+                        "letter" => value.string().map(|s| band.letter = s),
+                        "min" => value.number().map(|n| band.min = n),
+                        "max" => value.number().map(|n| band.max = n),
+                        "modifiers" => value.boolean().map(|b| band.modifiers = b),
+                        _ => Err(format!("unknown band field '{}'", key)),
+                    }
+                }
+            };
+            result.map_err(|e| format!("Line {}: {}", line_number, e))?;
+        }
+
+        if !bands.is_empty() {
+            scale.bands = bands;
+        }
+        scale.validate()?;
+        Ok(scale)
+    }
+}
+
+// The JSON version of the scale file has the same settings as the TOML one:
+//
+//     {
+//       "max_score": 100,
+//       "pass_mark": 50,
+//       "bands": [
+//         {"letter": "Pass", "min": 50, "max": 100},
+//         {"letter": "Fail", "min": 0, "max": 50, "modifiers": false}
+//       ]
+//     }
+impl FromJson for GradingScale {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        let mut scale = GradingScale::default();
+        if value.as_object().is_none() {
+            return Err(format!("expected an object, found {}", value));
+        }
+        // Reads an optional field, so anything left out keeps its default.
+        fn optional<T: FromJson>(value: &JsonValue, key: &str, target: &mut T) -> Result<(), String> {
+            if let Some(item) = value.get(key) {
+                *target = T::from_json(item).map_err(|e| format!("{}: {}", key, e))?;
+            }
+            Ok(())
+        }
+        optional(value, "max_score", &mut scale.max_score)?;
+        optional(value, "pass_mark", &mut scale.pass_mark)?;
+        optional(value, "plus_minus", &mut scale.plus_minus)?;
+        optional(value, "modifier_width", &mut scale.modifier_width)?;
+
+        if let Some(bands) = value.get("bands") {
+            let items = bands
+                .as_array()
+                .ok_or_else(|| String::from("bands: expected an array"))?;
+            scale.bands.clear();
+            for (i, item) in items.iter().enumerate() {
+                let mut band = Band::new("", 0.0, 0.0);
+                let read = |band: &mut Band| -> Result<(), String> {
+                    band.letter = String::from_json(item.field("letter")?)?;
+                    band.min = f64::from_json(item.field("min")?)?;
+                    band.max = f64::from_json(item.field("max")?)?;
+                    optional(item, "modifiers", &mut band.modifiers)
+                };
+                read(&mut band).map_err(|e| format!("bands[{}]: {}", i, e))?;
+                scale.bands.push(band);
+            }
+        }
+
+        scale.validate()?;
+        Ok(scale)
+    }
+}
+
+// Removes a # comment from the end of a line, unless the # is inside quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' if !in_quotes => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// The kinds of value the scale file can contain.
+enum TomlValue {
+    Number(f64),
+    Boolean(bool),
+    Text(String),
+}
+
+impl TomlValue {
+    fn parse(text: &str) -> Result<TomlValue, String> {
+        if text == "true" || text == "false" {
+            return Ok(TomlValue::Boolean(text == "true"));
+        }
+        if let Some(inner) = text.strip_prefix('"') {
+            return match inner.strip_suffix('"') {
+                Some(s) => Ok(TomlValue::Text(s.to_string())),
+                None => Err(format!("unclosed string {}", text)),
+            };
+        }
+        text.replace('_', "")
+            .parse::<f64>()
+            .map(TomlValue::Number)
+            .map_err(|_| format!("'{}' is not a number, true/false or a \"string\"", text))
+    }
+
+    fn number(&self) -> Result<f64, String> {
+        match self {
+            TomlValue::Number(n) => Ok(*n),
+            _ => Err(String::from("expected a number")),
+        }
+    }
+
+    fn boolean(&self) -> Result<bool, String> {
+        match self {
+            TomlValue::Boolean(b) => Ok(*b),
+            _ => Err(String::from("expected true or false")),
+        }
+    }
+
+    fn string(&self) -> Result<String, String> {
+        match self {
+            TomlValue::Text(s) => Ok(s.clone()),
+            _ => Err(String::from("expected a \"string\"")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nan_and_infinity_are_refused() {
+        let cases = [
+            ("max_score = nan", "max_score must be a normal number, not NaN"),
+            ("max_score = inf", "max_score must be a normal number, not inf"),
+            ("pass_mark = nan", "pass_mark must be a normal number, not NaN"),
+            ("modifier_width = nan", "modifier_width must be a normal number, not NaN"),
+            (
+                "[[band]]\nletter = \"Pass\"\nmin = 0\nmax = nan",
+                "Band Pass must start and end at normal numbers, not 0 and NaN",
+            ),
+            (
+                "[[band]]\nletter = \"Pass\"\nmin = -inf\nmax = 100",
+                "Band Pass must start and end at normal numbers, not -inf and 100",
+            ),
+        ];
+        for (text, message) in cases {
+            assert_eq!(GradingScale::from_toml(text).unwrap_err(), message);
+        }
+        assert!(GradingScale::from_toml("max_score = 100\npass_mark = 50").is_ok());
+    }
+}
@@ -0,0 +1,367 @@
+// Moving around a grid with the Direction enum from main.rs.
+//
+// In main.rs, Direction is only matched to print a sentence. Here it gets
+// methods for turning and for working out where a step goes, and there's a
+// Grid<T> to move around on. Rows go down the screen, so y gets bigger going
+// Down, and x gets bigger going Right:
+//
+//     (0, 0) (1, 0) (2, 0)
+//     (0, 1) (1, 1) (2, 1)
+//
+// A move script is a string of U, D, L and R, like "UUR DDL". A number before
+// a letter repeats it ("3R" is "RRR"), and diagonals are written as two letters
+// in brackets, like "(UR)". Spaces are ignored.
+
+use std::fmt;
+
+use crate::Direction;
+
+// An `impl` block for a type can be in any module of the same crate, so the
+// methods can live here even though Direction is defined in main.rs.
+impl Direction {
+    // Every direction, going clockwise from Up.
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::UpRight,
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+    ];
+
+    // The four directions that aren't diagonal.
+    pub const STRAIGHT: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    // Where this direction is in ALL. Turning is then just adding to the index.
+    fn clockwise_index(self) -> usize {
+        Direction::ALL.iter().position(|&d| d == self).unwrap_or(0)
+    }
+
+    // Turns 90 degrees clockwise: Up => Right, UpRight => DownRight.
+    pub fn turn_right(self) -> Direction {
+        Direction::ALL[(self.clockwise_index() + 2) % 8]
+    }
+
+    // Turns 90 degrees anticlockwise: Up => Left, UpRight => UpLeft.
+    pub fn turn_left(self) -> Direction {
+        Direction::ALL[(self.clockwise_index() + 6) % 8]
+    }
+
+    pub fn opposite(self) -> Direction {
+        Direction::ALL[(self.clockwise_index() + 4) % 8]
+    }
+
+    // How far one step moves in x and y.
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (1, -1),
+            Direction::DownLeft => (-1, 1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn is_diagonal(self) -> bool {
+        let (dx, dy) = self.delta();
+        dx != 0 && dy != 0
+    }
+
+    // The letter for a straight direction in a move script.
+    pub fn from_letter(letter: char) -> Option<Direction> {
+        match letter.to_ascii_uppercase() {
+            'U' => Some(Direction::Up),
+            'D' => Some(Direction::Down),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    // The character used when drawing a path.
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+            Direction::UpRight | Direction::DownLeft => '/',
+            Direction::UpLeft | Direction::DownRight => '\\',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn new(x: i32, y: i32) -> Position {
+        Position { x, y }
+    }
+
+    // The position one step away, ignoring any grid edges.
+    pub fn step(self, direction: Direction) -> Position {
+        let (dx, dy) = direction.delta();
+        Position::new(self.x + dx, self.y + dy)
+    }
+
+    // How many straight steps apart two positions are, like walking city blocks.
+    pub fn manhattan_distance(self, other: Position) -> u32 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+// What happens when a step would go off the edge of a grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edges {
+    // The step isn't allowed.
+    Bounded,
+    // The step comes back in on the other side, like in Pac-Man.
+    Wrapping,
+}
+
+// A limit so that a size like --size 3000000000x3 can't use up all the memory.
+const MAX_CELLS: usize = 10_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    // All the rows one after another: the cell at (x, y) is cells[y * width + x].
+    cells: Vec<T>,
+    edges: Edges,
+}
+
+impl<T: Clone> Grid<T> {
+    // A grid with every cell set to `fill`.
+    pub fn new(width: usize, height: usize, fill: T, edges: Edges) -> Result<Grid<T>, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("A grid can't be {}x{}; both sizes must be at least 1", width, height));
+        }
+        // Positions use i32, so each side has to fit in one. checked_mul gives
+        // None instead of panicking if width * height is too big for a usize.
+        let too_big = || format!("A grid can't be {}x{}; the most is {} cells", width, height, MAX_CELLS);
+        if width > i32::MAX as usize || height > i32::MAX as usize {
+            return Err(too_big());
+        }
+        let count = width.checked_mul(height).filter(|&count| count <= MAX_CELLS).ok_or_else(too_big)?;
+        Ok(Grid {
+            width,
+            height,
+            cells: vec![fill; count],
+            edges,
+        })
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[allow(dead_code)]
+    pub fn edges(&self) -> Edges {
+        self.edges
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        position.x >= 0 && position.y >= 0 && (position.x as usize) < self.width && (position.y as usize) < self.height
+    }
+
+    fn index(&self, position: Position) -> Option<usize> {
+        if self.contains(position) {
+            Some(position.y as usize * self.width + position.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        self.cells.get(self.index(position)?)
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        let index = self.index(position)?;
+        self.cells.get_mut(index)
+    }
+
+    pub fn set(&mut self, position: Position, value: T) -> Result<(), String> {
+        let cell = self
+            .get_mut(position)
+            .ok_or_else(|| format!("{} is outside the grid", position))?;
+        *cell = value;
+        Ok(())
+    }
+
+    // Where a step from `position` ends up, or None if it would leave a bounded grid.
+    pub fn step(&self, position: Position, direction: Direction) -> Option<Position> {
+        let next = position.step(direction);
+        match self.edges {
+            Edges::Bounded if self.contains(next) => Some(next),
+            Edges::Bounded => None,
+            // rem_euclid wraps -1 round to width - 1, where % would give -1.
+            Edges::Wrapping => Some(Position::new(
+                next.x.rem_euclid(self.width as i32),
+                next.y.rem_euclid(self.height as i32),
+            )),
+        }
+    }
+
+    // The positions one step away, straight only or with diagonals too.
+    pub fn neighbours(&self, position: Position, diagonals: bool) -> Vec<Position> {
+        let directions: &[Direction] = if diagonals { &Direction::ALL } else { &Direction::STRAIGHT };
+        directions.iter().filter_map(|&d| self.step(position, d)).collect()
+    }
+
+    // Follows the moves from `start`, returning every position visited (start included).
+    pub fn walk(&self, start: Position, moves: &[Direction]) -> Result<Vec<Position>, String> {
+        if !self.contains(start) {
+            return Err(format!("The start {} is outside the {}x{} grid", start, self.width, self.height));
+        }
+        let mut path = vec![start];
+        let mut position = start;
+        for (i, &direction) in moves.iter().enumerate() {
+            position = self
+                .step(position, direction)
+                .ok_or_else(|| format!("Move {} ({:?}) from {} goes off the grid", i + 1, direction, position))?;
+            path.push(position);
+        }
+        Ok(path)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+}
+
+impl Grid<char> {
+    // Marks a walked path: an arrow in each cell showing which way it was left,
+    // S at the start and E at the end.
+    pub fn draw_path(&mut self, path: &[Position], moves: &[Direction]) {
+        for (&position, direction) in path.iter().zip(moves) {
+            if let Some(cell) = self.get_mut(position) {
+                *cell = direction.arrow();
+            }
+        }
+        // The start is drawn last, so a path that ends where it began still shows S.
+        if let Some(cell) = path.last().and_then(|&end| self.get_mut(end)) {
+            *cell = 'E';
+        }
+        if let Some(cell) = path.first().and_then(|&start| self.get_mut(start)) {
+            *cell = 'S';
+        }
+    }
+}
+
+// A grid of characters prints as one line per row.
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            writeln!(f, "{}", row.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+// A limit so that a script like "999999999R" can't use up all the memory.
+const MAX_MOVES: usize = 10_000;
+
+// Turns a move script like "UUR 3D (DL)" into a list of directions.
+pub fn parse_moves(script: &str) -> Result<Vec<Direction>, String> {
+    let mut moves = Vec::new();
+    let mut chars = script.chars().enumerate().peekable();
+    // The number typed before a move, if any.
+    let mut count: Option<usize> = None;
+
+    while let Some((i, c)) = chars.next() {
+        let direction = if let Some(digit) = c.to_digit(10) {
+            count = Some(count.unwrap_or(0) * 10 + digit as usize);
+            if count > Some(MAX_MOVES) {
+                return Err(format!("The number at position {} is too big; the most is {}", i + 1, MAX_MOVES));
+            }
+            continue;
+        } else if c.is_whitespace() {
+            if count.is_some() {
+                return Err(format!("Expected a move after the number at position {}", i + 1));
+            }
+            continue;
+        } else if c == '(' {
+            // Read up to the closing bracket.
+            let mut letters = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, ')')) => break,
+                    Some((_, letter)) => letters.push(letter.to_ascii_uppercase()),
+                    None => return Err(format!("The bracket at position {} is never closed", i + 1)),
+                }
+            }
+            match letters.as_str() {
+                "UL" | "LU" => Direction::UpLeft,
+                "UR" | "RU" => Direction::UpRight,
+                "DL" | "LD" => Direction::DownLeft,
+                "DR" | "RD" => Direction::DownRight,
+                _ => {
+                    return Err(format!(
+                        "'({})' at position {} isn't a diagonal; use (UL), (UR), (DL) or (DR)",
+                        letters,
+                        i + 1
+                    ));
+                }
+            }
+        } else {
+            Direction::from_letter(c).ok_or_else(|| {
+                format!("'{}' at position {} isn't a move; use U, D, L, R or a diagonal like (UR)", c, i + 1)
+            })?
+        };
+
+        let times = count.take().unwrap_or(1);
+        if times == 0 {
+            return Err(format!("A move can't be repeated 0 times (at position {})", i + 1));
+        }
+        moves.extend(std::iter::repeat_n(direction, times));
+        if moves.len() > MAX_MOVES {
+            return Err(format!("The move script has more than {} moves", MAX_MOVES));
+        }
+    }
+
+    if count.is_some() {
+        return Err(String::from("The move script ends with a number but no move"));
+    }
+    Ok(moves)
+}
+
+// The smallest grid that fits a path, and where to start in it.
+// Returns (width, height, start).
+pub fn fit_path(moves: &[Direction]) -> (usize, usize, Position) {
+    let mut position = Position::new(0, 0);
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (0, 0, 0, 0);
+    for &direction in moves {
+        position = position.step(direction);
+        min_x = min_x.min(position.x);
+        max_x = max_x.max(position.x);
+        min_y = min_y.min(position.y);
+        max_y = max_y.max(position.y);
+    }
+    let width = (max_x - min_x + 1) as usize;
+    let height = (max_y - min_y + 1) as usize;
+    (width, height, Position::new(-min_x, -min_y))
+}
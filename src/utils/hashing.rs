@@ -0,0 +1,247 @@
+// SHA-256, written out by hand, and a few helpers for working with hashes.
+//
+// A hash function turns any amount of data into a short fixed-size "digest"
+// (32 bytes for SHA-256). The same input always gives the same digest, but
+// changing even one bit of the input changes the whole digest, and there's
+// no way to work backwards from a digest to the input.
+//
+// SHA-256 works on the message in 64-byte blocks. The message is "padded":
+// a 1 bit is added, then 0 bits, then the message length, so the total is a
+// whole number of blocks. Each block is then mixed into 8 running u32 values
+// (the "state") with lots of rotating, shifting and adding. The final state
+// is the digest.
+//
+// The numbers in K and INITIAL_STATE come from the standard (FIPS 180-4).
+// They are the fractional parts of the cube and square roots of the first
+// prime numbers, chosen so that nobody could have hidden a trick in them.
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+// The size of a block, in bytes.
+const BLOCK_SIZE: usize = 64;
+
+// Hashes data that arrives in pieces: call update() as many times as you
+// like, then finish() to get the digest. Hashing "ab" in one go or as "a"
+// then "b" gives the same digest.
+#[derive(Debug, Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    // Bytes waiting to make up a full block.
+    buffer: Vec<u8>,
+    // How many bytes have been added in total.
+    length: u64,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Sha256::new()
+    }
+}
+
+impl Sha256 {
+    pub fn new() -> Sha256 {
+        Sha256 {
+            state: INITIAL_STATE,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            length: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= BLOCK_SIZE {
+            let full = self.buffer.len() - self.buffer.len() % BLOCK_SIZE;
+            for block in self.buffer[..full].chunks_exact(BLOCK_SIZE) {
+                compress(&mut self.state, block);
+            }
+            self.buffer.drain(..full);
+        }
+    }
+
+    pub fn finish(mut self) -> [u8; 32] {
+        let bit_length = self.length.wrapping_mul(8);
+        // The padding: a 1 bit (0x80 is 10000000), then zeros until there are
+        // 8 bytes left in the block, then the length in bits.
+        let mut padding = vec![0x80];
+        while (self.buffer.len() + padding.len()) % BLOCK_SIZE != BLOCK_SIZE - 8 {
+            padding.push(0);
+        }
+        padding.extend_from_slice(&bit_length.to_be_bytes());
+        self.update(&padding);
+
+        let mut digest = [0; 32];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+}
+
+// Mixes one 64-byte block into the state.
+fn compress(state: &mut [u32; 8], block: &[u8]) {
+    // The block is read as 16 big-endian u32s, then stretched out to 64.
+    let mut w = [0u32; 64];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        // wrapping_add, because the additions are meant to overflow and wrap round.
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choose = (e & f) ^ (!e & g);
+        let temp1 = h.wrapping_add(s1).wrapping_add(choose).wrapping_add(K[i]).wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(majority);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+    for (value, change) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *value = value.wrapping_add(change);
+    }
+}
+
+// Hashes some data in one go.
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finish()
+}
+
+// Writes bytes as lowercase hex, two characters per byte: [255, 1] => "ff01".
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) {
+        return Err(format!("'{}' isn't hex: it has an odd number of characters", text));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            text.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("'{}' isn't hex", text))
+        })
+        .collect()
+}
+
+// Compares two byte strings without stopping at the first difference.
+// A normal == returns sooner the earlier the first wrong byte is, and an
+// attacker timing lots of guesses could use that to work out a hash or a
+// signature one byte at a time.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+// SHA-1 is SHA-256's older, smaller sibling: a 20-byte digest and 80 simpler
+// steps per block. It's no longer safe for signing documents, but HMAC-SHA-1
+// is still fine, and it's what authenticator apps use for one-time codes.
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    // The same padding as SHA-256: a 1 bit, zeros, then the length in bits.
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % BLOCK_SIZE != BLOCK_SIZE - 8 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64).wrapping_mul(8).to_be_bytes());
+
+    let mut state: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    for block in message.chunks_exact(BLOCK_SIZE) {
+        let mut w = [0u32; 80];
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, &word) in w.iter().enumerate() {
+            // Each quarter of the steps mixes b, c and d in a different way.
+            let (mixed, constant) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(mixed).wrapping_add(e).wrapping_add(constant).wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (value, change) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(change);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+// HMAC (RFC 2104) turns a hash function into a signature: only someone who
+// knows the key can make the right HMAC for a message, and changing the
+// message changes the HMAC. It hashes the key and message together twice,
+// with the key mixed with two different padding bytes, because hashing
+// key + message just once can be extended by an attacker.
+//
+// `hash` is the hash function to use. SHA-1 and SHA-256 both work on 64-byte
+// blocks, so the same code works for both; N is the size of the digest.
+fn hmac<const N: usize>(hash: fn(&[u8]) -> [u8; N], key: &[u8], message: &[u8]) -> [u8; N] {
+    // Keys longer than a block are hashed first; shorter ones are padded with zeros.
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..N].copy_from_slice(&hash(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = block.map(|b| b ^ 0x36).to_vec();
+    inner.extend_from_slice(message);
+    let mut outer = block.map(|b| b ^ 0x5c).to_vec();
+    outer.extend_from_slice(&hash(&inner));
+    hash(&outer)
+}
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    hmac(sha256, key, message)
+}
+
+pub fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    hmac(sha1, key, message)
+}
@@ -0,0 +1,279 @@
+// Undo and redo for changes to a Vec or a HashMap.
+//
+// The vectors lesson in main.rs pushes, pops, inserts and clears veggies, and
+// once a change is made there's no going back. History<T> wraps a collection
+// and makes every change through an "edit" value instead:
+//
+//     let mut veggies = History::new(vec!["Carrot", "Potato"]);
+//     veggies.apply(VecEdit::Push("Tomato"))?;   // ["Carrot", "Potato", "Tomato"]
+//     veggies.undo()?;                            // ["Carrot", "Potato"]
+//     veggies.redo()?;                            // ["Carrot", "Potato", "Tomato"]
+//
+// Applying an edit gives back the edit that reverses it (the reverse of
+// Push is Pop, the reverse of Remove(0) is Insert(0, the old item), and so on).
+// History keeps both, so undo applies the reverse and redo applies the
+// original again. Making a new change after an undo throws away the steps that
+// could have been redone, like in a text editor.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+// A collection that can be changed with edits. `apply` makes the change and
+// returns the edit that would undo it, or an error if the edit doesn't fit
+// (like removing index 5 from a Vec with 3 items). On an error nothing changes.
+pub trait Editable {
+    type Edit: Clone + fmt::Display;
+
+    fn apply(&mut self, edit: &Self::Edit) -> Result<Self::Edit, String>;
+}
+
+// The changes you can make to a Vec. They match the methods from the vectors lesson.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VecEdit<T> {
+    Push(T),
+    Pop,
+    Insert(usize, T),
+    Remove(usize),
+    // veggies[1] = "Spinach"
+    Set(usize, T),
+    // Resize(5, "Lettuce") grows or shrinks to 5 items, filling new slots with "Lettuce".
+    #[allow(dead_code)]
+    Resize(usize, T),
+    // Adds several items to the end.
+    Extend(Vec<T>),
+    // Shortens the Vec to this many items.
+    Truncate(usize),
+    Clear,
+}
+
+// The changes you can make to a HashMap.
+#[derive(Debug, Clone)]
+pub enum MapEdit<K, V> {
+    // Adds a key, or replaces its value if it's already there.
+    Insert(K, V),
+    Remove(K),
+    // Replaces the whole map. This is how a Clear is undone.
+    Replace(HashMap<K, V>),
+    #[allow(dead_code)]
+    Clear,
+}
+
+impl<T: fmt::Debug> fmt::Display for VecEdit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VecEdit::Push(item) => write!(f, "push {:?}", item),
+            VecEdit::Pop => write!(f, "pop"),
+            VecEdit::Insert(index, item) => write!(f, "insert {:?} at {}", item, index),
+            VecEdit::Remove(index) => write!(f, "remove index {}", index),
+            VecEdit::Set(index, item) => write!(f, "set index {} to {:?}", index, item),
+            VecEdit::Resize(len, fill) => write!(f, "resize to {} with {:?}", len, fill),
+            VecEdit::Extend(items) => write!(f, "extend with {:?}", items),
+            VecEdit::Truncate(len) => write!(f, "truncate to {}", len),
+            VecEdit::Clear => write!(f, "clear"),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Display for MapEdit<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapEdit::Insert(key, value) => write!(f, "insert {:?} => {:?}", key, value),
+            MapEdit::Remove(key) => write!(f, "remove {:?}", key),
+            MapEdit::Replace(map) => write!(f, "replace with {} entries", map.len()),
+            MapEdit::Clear => write!(f, "clear"),
+        }
+    }
+}
+
+impl<T: Clone + fmt::Debug> Editable for Vec<T> {
+    type Edit = VecEdit<T>;
+
+    fn apply(&mut self, edit: &VecEdit<T>) -> Result<VecEdit<T>, String> {
+        let len = self.len();
+        let out_of_range = |index: usize| format!("Index {} is out of range for a length of {}", index, len);
+
+        match edit {
+            VecEdit::Push(item) => {
+                self.push(item.clone());
+                Ok(VecEdit::Pop)
+            }
+            VecEdit::Pop => match self.pop() {
+                Some(item) => Ok(VecEdit::Push(item)),
+                None => Err(String::from("Can't pop from an empty Vec")),
+            },
+            VecEdit::Insert(index, item) => {
+                // insert() allows index == len, which is the same as push().
+                if *index > len {
+                    return Err(out_of_range(*index));
+                }
+                self.insert(*index, item.clone());
+                Ok(VecEdit::Remove(*index))
+            }
+            VecEdit::Remove(index) => {
+                if *index >= len {
+                    return Err(out_of_range(*index));
+                }
+                let item = self.remove(*index);
+                Ok(VecEdit::Insert(*index, item))
+            }
+            VecEdit::Set(index, item) => {
+                let slot = self.get_mut(*index).ok_or_else(|| out_of_range(*index))?;
+                // replace() puts the new value in and hands back the old one.
+                let old = std::mem::replace(slot, item.clone());
+                Ok(VecEdit::Set(*index, old))
+            }
+            VecEdit::Resize(new_len, fill) => {
+                // Growing is undone by cutting back; shrinking by putting the lost items back.
+                let undo = if *new_len >= len {
+                    VecEdit::Truncate(len)
+                } else {
+                    VecEdit::Extend(self[*new_len..].to_vec())
+                };
+                self.resize(*new_len, fill.clone());
+                Ok(undo)
+            }
+            VecEdit::Extend(items) => {
+                self.extend(items.iter().cloned());
+                Ok(VecEdit::Truncate(len))
+            }
+            VecEdit::Truncate(new_len) => {
+                if *new_len > len {
+                    return Err(format!("Can't truncate a length of {} to {}", len, new_len));
+                }
+                // split_off() cuts the Vec in two and returns the second part.
+                Ok(VecEdit::Extend(self.split_off(*new_len)))
+            }
+            VecEdit::Clear => Ok(VecEdit::Extend(std::mem::take(self))),
+        }
+    }
+}
+
+impl<K, V> Editable for HashMap<K, V>
+where
+    K: Clone + Eq + Hash + fmt::Debug,
+    V: Clone + fmt::Debug,
+{
+    type Edit = MapEdit<K, V>;
+
+    fn apply(&mut self, edit: &MapEdit<K, V>) -> Result<MapEdit<K, V>, String> {
+        match edit {
+            // insert() returns the old value if the key was already there.
+            MapEdit::Insert(key, value) => match self.insert(key.clone(), value.clone()) {
+                Some(old) => Ok(MapEdit::Insert(key.clone(), old)),
+                None => Ok(MapEdit::Remove(key.clone())),
+            },
+            MapEdit::Remove(key) => match self.remove(key) {
+                Some(old) => Ok(MapEdit::Insert(key.clone(), old)),
+                None => Err(format!("There is no key {:?}", key)),
+            },
+            MapEdit::Replace(map) => Ok(MapEdit::Replace(std::mem::replace(self, map.clone()))),
+            MapEdit::Clear => Ok(MapEdit::Replace(std::mem::take(self))),
+        }
+    }
+}
+
+// One change that has been made: the edit itself and the edit that reverses it.
+#[derive(Debug, Clone)]
+struct Step<E> {
+    edit: E,
+    reverse: E,
+}
+
+#[derive(Debug, Clone)]
+pub struct History<T: Editable> {
+    value: T,
+    done: Vec<Step<T::Edit>>,
+    undone: Vec<Step<T::Edit>>,
+    // How many steps undo can go back. None means there is no limit.
+    max_depth: Option<usize>,
+    // A line of text for every apply, undo and redo, oldest first.
+    log: Vec<String>,
+}
+
+impl<T: Editable> History<T> {
+    pub fn new(value: T) -> History<T> {
+        History {
+            value,
+            done: Vec::new(),
+            undone: Vec::new(),
+            max_depth: None,
+            log: Vec::new(),
+        }
+    }
+
+    // Only the last `max_depth` changes can be undone. Older ones are forgotten.
+    pub fn with_max_depth(value: T, max_depth: usize) -> History<T> {
+        History {
+            max_depth: Some(max_depth),
+            ..History::new(value)
+        }
+    }
+
+    // The collection as it is now.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    // Gives back the collection and throws the history away.
+    #[allow(dead_code)]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    pub fn log(&self) -> &[String] {
+        &self.log
+    }
+
+    pub fn apply(&mut self, edit: T::Edit) -> Result<(), String> {
+        let reverse = self.value.apply(&edit)?;
+        let mut line = edit.to_string();
+        if !self.undone.is_empty() {
+            // This change starts a new branch, so the old redo steps can't be used any more.
+            line.push_str(&format!(" (dropped {} redo steps)", self.undone.len()));
+            self.undone.clear();
+        }
+        self.log.push(line);
+        self.done.push(Step { edit, reverse });
+        if let Some(max) = self.max_depth
+            && self.done.len() > max
+        {
+            self.done.remove(0);
+        }
+        Ok(())
+    }
+
+    // Reverses the last change. Returns a description of what was undone.
+    pub fn undo(&mut self) -> Result<String, String> {
+        let step = self.done.pop().ok_or_else(|| String::from("There is nothing to undo"))?;
+        if let Err(e) = self.value.apply(&step.reverse) {
+            self.done.push(step);
+            return Err(e);
+        }
+        let line = format!("undo {} (by {})", step.edit, step.reverse);
+        self.log.push(line.clone());
+        self.undone.push(step);
+        Ok(line)
+    }
+
+    // Makes the last undone change again. Returns a description of what was redone.
+    pub fn redo(&mut self) -> Result<String, String> {
+        let step = self.undone.pop().ok_or_else(|| String::from("There is nothing to redo"))?;
+        if let Err(e) = self.value.apply(&step.edit) {
+            self.undone.push(step);
+            return Err(e);
+        }
+        let line = format!("redo {}", step.edit);
+        self.log.push(line.clone());
+        self.done.push(step);
+        Ok(line)
+    }
+}
@@ -0,0 +1,714 @@
+// A small JSON library: a value type, a parser and a pretty-printer.
+//
+// JSON is the text format most web APIs and config files use:
+//
+//     {"name": "John", "age": 36, "languages": ["Rust", "Python"]}
+//
+// The ToJson and FromJson traits convert our own types (Person, Direction,
+// LoginStatus and the HashMaps from the lessons) to and from a JsonValue.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Direction, LoginStatus, Person};
+
+// Any piece of JSON. An enum is a good fit because a JSON value is exactly one
+// of these kinds, and arrays and objects can hold more values inside them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    // A Vec of pairs rather than a HashMap, so keys come out in the order they went in.
+    Object(Vec<(String, JsonValue)>),
+}
+
+// Where the parser got stuck. Lines and columns start at 1, like in a text editor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+// Parses JSON text into a JsonValue.
+pub fn parse(text: &str) -> Result<JsonValue, JsonError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+    };
+    parser.skip_whitespace();
+    let value = parser.parse_value(0)?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected text after the end of the JSON value"));
+    }
+    Ok(value)
+}
+
+// Arrays inside arrays inside arrays... Stop before a very deep file uses up the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError {
+            line: self.line,
+            column: self.column,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    // Moves past one character, keeping track of the line and column.
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("expected '{}' but found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}' but the text ended", expected))),
+        }
+    }
+
+    // Reads a keyword like true, false or null.
+    fn keyword(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, JsonError> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("expected '{}'", word)));
+            }
+            self.next();
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("the JSON is nested too deeply"));
+        }
+        match self.peek() {
+            Some('{') => self.parse_object(depth),
+            Some('[') => self.parse_array(depth),
+            Some('"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some('t') => self.keyword("true", JsonValue::Bool(true)),
+            Some('f') => self.keyword("false", JsonValue::Bool(false)),
+            Some('n') => self.keyword("null", JsonValue::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("expected a value but the text ended")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        self.expect('{')?;
+        let mut pairs: Vec<(String, JsonValue)> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(JsonValue::Object(pairs));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a \"key\" in quotes"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            let value = self.parse_value(depth + 1)?;
+            pairs.push((key, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.next();
+                }
+                Some('}') => {
+                    self.next();
+                    return Ok(JsonValue::Object(pairs));
+                }
+                _ => return Err(self.error("expected ',' or '}' after the value")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<JsonValue, JsonError> {
+        self.expect('[')?;
+        let mut items: Vec<JsonValue> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.next();
+                }
+                Some(']') => {
+                    self.next();
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']' after the value")),
+            }
+        }
+    }
+
+    // Reads the four hex digits after \u.
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = self
+                .peek()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("expected 4 hex digits after \\u"))?;
+            self.next();
+            value = value * 16 + digit;
+        }
+        Ok(value)
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("the string is never closed")),
+            };
+            if (c as u32) < 0x20 {
+                return Err(self.error("strings can't contain raw control characters like new lines; use \\n"));
+            }
+            self.next();
+            match c {
+                '"' => return Ok(text),
+                '\\' => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("unknown escape after \\")),
+                    };
+                    text.push(escaped);
+                }
+                _ => text.push(c),
+            }
+        }
+    }
+
+    // \uXXXX escapes. Characters outside the Basic Multilingual Plane (like most emoji)
+    // are written as two escapes, a "surrogate pair", which we join back together.
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let first = self.parse_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&first) {
+            if self.next() != Some('\\') || self.next() != Some('u') {
+                return Err(self.error("expected a second \\u escape to finish the surrogate pair"));
+            }
+            let second = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&second) {
+                return Err(self.error("invalid low surrogate in \\u escape"));
+            }
+            0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+        } else {
+            first
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid \\u escape"))
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.pos;
+        let (line, column) = (self.line, self.column);
+        let take_digits = |parser: &mut Parser| -> usize {
+            let mut count = 0;
+            while parser.peek().is_some_and(|c| c.is_ascii_digit()) {
+                parser.next();
+                count += 1;
+            }
+            count
+        };
+
+        if self.peek() == Some('-') {
+            self.next();
+        }
+        // JSON doesn't allow leading zeros like 012.
+        if self.peek() == Some('0') {
+            self.next();
+            if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.error("numbers can't have leading zeros"));
+            }
+        } else if take_digits(self) == 0 {
+            return Err(self.error("expected a digit"));
+        }
+        if self.peek() == Some('.') {
+            self.next();
+            if take_digits(self) == 0 {
+                return Err(self.error("expected a digit after the decimal point"));
+            }
+        }
+        if let Some('e' | 'E') = self.peek() {
+            self.next();
+            if let Some('+' | '-') = self.peek() {
+                self.next();
+            }
+            if take_digits(self) == 0 {
+                return Err(self.error("expected a digit in the exponent"));
+            }
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match text.parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(JsonValue::Number(n)),
+            _ => Err(JsonError {
+                line,
+                column,
+                message: format!("'{}' is not a number we can store in an f64", text),
+            }),
+        }
+    }
+}
+
+// Writes a string with quotes around it and escapes for special characters.
+fn write_string(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_number(n: f64, out: &mut String) {
+    if !n.is_finite() {
+        // JSON has no NaN or infinity.
+        out.push_str("null");
+    } else if n.fract() == 0.0 && n.abs() < 1e15 {
+        // Whole numbers are written without ".0", so 36.0 becomes 36.
+        out.push_str(&format!("{}", n as i64));
+    } else {
+        out.push_str(&format!("{}", n));
+    }
+}
+
+impl JsonValue {
+    // Writes the value on one line with no extra spaces.
+    fn write_compact(&self, out: &mut String) {
+        match self {
+            JsonValue::Null => out.push_str("null"),
+            JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            JsonValue::Number(n) => write_number(*n, out),
+            JsonValue::String(s) => write_string(s, out),
+            JsonValue::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    item.write_compact(out);
+                }
+                out.push(']');
+            }
+            JsonValue::Object(pairs) => {
+                out.push('{');
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_string(key, out);
+                    out.push(':');
+                    value.write_compact(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+
+    fn write_pretty(&self, indent: usize, out: &mut String) {
+        let inner = "  ".repeat(indent + 1);
+        let outer = "  ".repeat(indent);
+        match self {
+            JsonValue::Array(items) if !items.is_empty() => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&inner);
+                    item.write_pretty(indent + 1, out);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&outer);
+                out.push(']');
+            }
+            JsonValue::Object(pairs) if !pairs.is_empty() => {
+                out.push_str("{\n");
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    out.push_str(&inner);
+                    write_string(key, out);
+                    out.push_str(": ");
+                    value.write_pretty(indent + 1, out);
+                    out.push_str(if i + 1 < pairs.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&outer);
+                out.push('}');
+            }
+            // Everything else looks the same either way.
+            _ => self.write_compact(out),
+        }
+    }
+
+    // Writes the value over several lines, indented by two spaces per level.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(0, &mut out);
+        out
+    }
+
+    // Looks up a key in an object. Returns None for other kinds of value.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(pairs) => pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Vec<(String, JsonValue)>> {
+        match self {
+            JsonValue::Object(pairs) => Some(pairs),
+            _ => None,
+        }
+    }
+
+    // Like get(), but an error naming the key if it's missing.
+    pub fn field(&self, key: &str) -> Result<&JsonValue, String> {
+        self.get(key).ok_or_else(|| format!("missing the \"{}\" field", key))
+    }
+}
+
+// Display gives the compact form, so you can use {} or .to_string().
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        self.write_compact(&mut out);
+        write!(f, "{}", out)
+    }
+}
+
+// Types that can turn themselves into JSON.
+pub trait ToJson {
+    fn to_json(&self) -> JsonValue;
+}
+
+// Types that can be built from JSON. The error says what didn't match.
+pub trait FromJson: Sized {
+    fn from_json(value: &JsonValue) -> Result<Self, String>;
+}
+
+impl ToJson for i32 {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Number(*self as f64)
+    }
+}
+
+impl FromJson for i32 {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        match value.as_f64() {
+            Some(n) if n.fract() == 0.0 && n >= i32::MIN as f64 && n <= i32::MAX as f64 => Ok(n as i32),
+            _ => Err(format!("expected a whole number, found {}", value)),
+        }
+    }
+}
+
+impl ToJson for u32 {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Number(*self as f64)
+    }
+}
+
+impl FromJson for u32 {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        match value.as_f64() {
+            Some(n) if n.fract() == 0.0 && n >= 0.0 && n <= u32::MAX as f64 => Ok(n as u32),
+            _ => Err(format!("expected a whole number of 0 or more, found {}", value)),
+        }
+    }
+}
+
+// JSON numbers are f64s, which only hold whole numbers exactly up to 2^53.
+// That's still far more seconds than a timestamp will ever need, but bigger
+// values are refused rather than quietly rounded.
+const MAX_EXACT_WHOLE_NUMBER: f64 = 9_007_199_254_740_992.0;
+
+impl ToJson for u64 {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Number(*self as f64)
+    }
+}
+
+impl FromJson for u64 {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        match value.as_f64() {
+            Some(n) if n.fract() == 0.0 && (0.0..=MAX_EXACT_WHOLE_NUMBER).contains(&n) => Ok(n as u64),
+            _ => Err(format!("expected a whole number of 0 or more, found {}", value)),
+        }
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Number(*self)
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        value.as_f64().ok_or_else(|| format!("expected a number, found {}", value))
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Bool(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        value.as_bool().ok_or_else(|| format!("expected true or false, found {}", value))
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::String(self.clone())
+    }
+}
+
+// &str can be written but not read back, because a &str has to borrow from somewhere.
+impl ToJson for &str {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::String(self.to_string())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        value
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| format!("expected a string, found {}", value))
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Array(self.iter().map(|item| item.to_json()).collect())
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        let items = value
+            .as_array()
+            .ok_or_else(|| format!("expected an array, found {}", value))?;
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| T::from_json(item).map_err(|e| format!("item {}: {}", i, e)))
+            .collect()
+    }
+}
+
+// A HashMap becomes a JSON object. This covers both the scores map (&str => i32)
+// and the capital cities map (&str => &str) from the lessons.
+// HashMaps have no order, so the keys are sorted to make the output the same every time.
+impl<K: AsRef<str>, V: ToJson> ToJson for HashMap<K, V> {
+    fn to_json(&self) -> JsonValue {
+        let mut pairs: Vec<(String, JsonValue)> = self
+            .iter()
+            .map(|(k, v)| (k.as_ref().to_string(), v.to_json()))
+            .collect();
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        JsonValue::Object(pairs)
+    }
+}
+
+impl<V: FromJson> FromJson for HashMap<String, V> {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        let pairs = value
+            .as_object()
+            .ok_or_else(|| format!("expected an object, found {}", value))?;
+        let mut map = HashMap::new();
+        for (key, item) in pairs {
+            let item = V::from_json(item).map_err(|e| format!("\"{}\": {}", key, e))?;
+            map.insert(key.clone(), item);
+        }
+        Ok(map)
+    }
+}
+
+impl ToJson for Person {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            (String::from("name"), self.name.to_json()),
+            (String::from("age"), self.age.to_json()),
+        ])
+    }
+}
+
+impl FromJson for Person {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        Ok(Person {
+            name: String::from_json(value.field("name")?).map_err(|e| format!("name: {}", e))?,
+            age: u32::from_json(value.field("age")?).map_err(|e| format!("age: {}", e))?,
+        })
+    }
+}
+
+// A Direction is written as its name, e.g. "Up".
+impl ToJson for Direction {
+    fn to_json(&self) -> JsonValue {
+        let name = match self {
+            Direction::Up => "Up",
+            Direction::Down => "Down",
+            Direction::Left => "Left",
+            Direction::Right => "Right",
+            Direction::UpLeft => "UpLeft",
+            Direction::UpRight => "UpRight",
+            Direction::DownLeft => "DownLeft",
+            Direction::DownRight => "DownRight",
+        };
+        JsonValue::String(name.to_string())
+    }
+}
+
+impl FromJson for Direction {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        match value.as_str() {
+            Some("Up") => Ok(Direction::Up),
+            Some("Down") => Ok(Direction::Down),
+            Some("Left") => Ok(Direction::Left),
+            Some("Right") => Ok(Direction::Right),
+            Some("UpLeft") => Ok(Direction::UpLeft),
+            Some("UpRight") => Ok(Direction::UpRight),
+            Some("DownLeft") => Ok(Direction::DownLeft),
+            Some("DownRight") => Ok(Direction::DownRight),
+            _ => Err(format!("expected a direction like \"Up\" or \"DownLeft\", found {}", value)),
+        }
+    }
+}
+
+// Enum variants with data are written as {"status": "Success", "message": "..."}.
+// Each variant names its data: a message, a username, or the seconds left on a lock.
+impl ToJson for LoginStatus {
+    fn to_json(&self) -> JsonValue {
+        let (status, key, data) = match self {
+            LoginStatus::Success(message) => ("Success", "message", message.to_json()),
+            LoginStatus::Error(message) => ("Error", "message", message.to_json()),
+            LoginStatus::Locked(seconds) => ("Locked", "seconds", seconds.to_json()),
+            LoginStatus::Expired(username) => ("Expired", "username", username.to_json()),
+            LoginStatus::MfaRequired(username) => ("MfaRequired", "username", username.to_json()),
+        };
+        JsonValue::Object(vec![
+            (String::from("status"), status.to_json()),
+            (String::from(key), data),
+        ])
+    }
+}
+
+impl FromJson for LoginStatus {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        let text = |key: &str| -> Result<String, String> {
+            String::from_json(value.field(key)?).map_err(|e| format!("{}: {}", key, e))
+        };
+        match value.field("status")?.as_str() {
+            Some("Success") => Ok(LoginStatus::Success(text("message")?)),
+            Some("Error") => Ok(LoginStatus::Error(text("message")?)),
+            Some("Locked") => {
+                let seconds = u64::from_json(value.field("seconds")?).map_err(|e| format!("seconds: {}", e))?;
+                Ok(LoginStatus::Locked(seconds))
+            }
+            Some("Expired") => Ok(LoginStatus::Expired(text("username")?)),
+            Some("MfaRequired") => Ok(LoginStatus::MfaRequired(text("username")?)),
+            _ => Err(String::from("status must be \"Success\", \"Error\", \"Locked\", \"Expired\" or \"MfaRequired\"")),
+        }
+    }
+}
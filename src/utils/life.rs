@@ -0,0 +1,409 @@
+// Conway's Game of Life, and other "life-like" cellular automata.
+//
+// The world is a grid of cells that are either alive or dead. Each generation,
+// every cell counts how many of its 8 neighbours are alive, and the rule says
+// what happens next. The rule is written like "B3/S23":
+//
+// - B3: a dead cell with exactly 3 live neighbours is born.
+// - S23: a live cell with 2 or 3 live neighbours survives. Every other live cell dies.
+//
+// B3/S23 is Conway's original. Others, like "B36/S23" (HighLife), give
+// different patterns. The edges of the world can be bounded (everything outside
+// is dead) or toroidal (the left edge joins the right and the top joins the
+// bottom, like Edges::Wrapping in the grid module).
+//
+// Patterns can be loaded from two common file formats:
+//
+//   Plaintext (.cells)    RLE (.rle)
+//   !Name: Glider         #N Glider
+//   .O.                   x = 3, y = 3, rule = B3/S23
+//   ..O                   bob$2bo$3o!
+//   OOO
+//
+// In RLE, b is a dead cell, o is a live one, $ ends a row and ! ends the
+// pattern. A number in front repeats the next thing, so 3o is ooo.
+
+use std::fmt;
+
+use crate::utils::grid::{Edges, Grid, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rule {
+    // born[n] is true if a dead cell with n live neighbours comes alive.
+    born: [bool; 9],
+    // survives[n] is true if a live cell with n live neighbours stays alive.
+    survives: [bool; 9],
+}
+
+impl Rule {
+    pub fn conway() -> Rule {
+        Rule::parse("B3/S23").expect("B3/S23 is a valid rule")
+    }
+
+    // Reads a rule like "B3/S23". The two halves can be either way round.
+    pub fn parse(text: &str) -> Result<Rule, String> {
+        let mut born = None;
+        let mut survives = None;
+        for part in text.trim().split('/') {
+            let mut chars = part.chars();
+            let target = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => &mut born,
+                Some('S') => &mut survives,
+                _ => return Err(format!("'{}' should start with B or S, like B3/S23", part)),
+            };
+            let mut counts = [false; 9];
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 => counts[n as usize] = true,
+                    _ => return Err(format!("'{}' in rule '{}' isn't a neighbour count from 0 to 8", c, text)),
+                }
+            }
+            if target.replace(counts).is_some() {
+                return Err(format!("Rule '{}' has the same part twice", text));
+            }
+        }
+        match (born, survives) {
+            (Some(born), Some(survives)) => Ok(Rule { born, survives }),
+            _ => Err(format!("Rule '{}' needs a B part and an S part, like B3/S23", text)),
+        }
+    }
+
+    // Whether a cell is alive next generation.
+    pub fn next_state(&self, alive: bool, neighbours: usize) -> bool {
+        if alive { self.survives[neighbours] } else { self.born[neighbours] }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |counts: &[bool; 9]| -> String {
+            (0..9).filter(|&n| counts[n]).map(|n| n.to_string()).collect()
+        };
+        write!(f, "B{}/S{}", digits(&self.born), digits(&self.survives))
+    }
+}
+
+// A pattern read from a file: its size, the live cells (from its top left
+// corner), and the rule if the file gave one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub alive: Vec<Position>,
+    pub rule: Option<Rule>,
+}
+
+impl Pattern {
+    // Builds a pattern from the live cells, working out its size.
+    fn from_cells(alive: Vec<Position>, rule: Option<Rule>) -> Pattern {
+        let width = alive.iter().map(|p| p.x + 1).max().unwrap_or(0) as usize;
+        let height = alive.iter().map(|p| p.y + 1).max().unwrap_or(0) as usize;
+        Pattern { width, height, alive, rule }
+    }
+
+    // Guesses the format: RLE files have an "x = " header line.
+    pub fn parse(text: &str) -> Result<Pattern, String> {
+        let is_rle = text
+            .lines()
+            .map(|line| line.trim())
+            .any(|line| line.starts_with("x ") || line.starts_with("x="));
+        if is_rle { Pattern::from_rle(text) } else { Pattern::from_plaintext(text) }
+    }
+
+    // Plaintext: O (or *) is alive, . is dead, and lines starting with ! are comments.
+    pub fn from_plaintext(text: &str) -> Result<Pattern, String> {
+        let mut alive = Vec::new();
+        let rows = text.lines().filter(|line| !line.starts_with('!'));
+        for (y, line) in rows.enumerate() {
+            for (x, c) in line.trim_end().chars().enumerate() {
+                match c {
+                    'O' | 'o' | '*' => alive.push(Position::new(x as i32, y as i32)),
+                    '.' => {}
+                    _ => return Err(format!("Unexpected '{}' in row {} of the pattern; use O for alive and . for dead", c, y + 1)),
+                }
+            }
+        }
+        Ok(Pattern::from_cells(alive, None))
+    }
+
+    pub fn from_rle(text: &str) -> Result<Pattern, String> {
+        let mut rule = None;
+        let mut body = String::new();
+        let mut found_header = false;
+        for line in text.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if !found_header {
+                // The header looks like "x = 3, y = 3, rule = B3/S23". Only the rule is needed,
+                // because the size can be worked out from the cells.
+                found_header = true;
+                for setting in line.split(',') {
+                    if let Some((name, value)) = setting.split_once('=')
+                        && name.trim() == "rule"
+                    {
+                        rule = Some(Rule::parse(value)?);
+                    }
+                }
+                continue;
+            }
+            body.push_str(line);
+        }
+        if !found_header {
+            return Err(String::from("The RLE pattern has no 'x = ..., y = ...' header line"));
+        }
+
+        let mut alive = Vec::new();
+        let (mut x, mut y) = (0, 0);
+        let mut count: Option<i32> = None;
+        for c in body.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit as i32));
+                continue;
+            }
+            let times = count.take().unwrap_or(1);
+            if times > MAX_RUN {
+                return Err(format!("A run of {} cells is too long; the most is {}", times, MAX_RUN));
+            }
+            // Every run moves right or down, so check the pattern hasn't grown too big.
+            let too_big = || format!("The RLE pattern is more than {} cells wide or tall", MAX_PATTERN_SIDE);
+            match c {
+                'b' => x = advance(x, times).ok_or_else(too_big)?,
+                'o' => {
+                    if advance(x, times).is_none() {
+                        return Err(too_big());
+                    }
+                    if alive.len() + times as usize > MAX_PATTERN_ALIVE {
+                        return Err(format!("The RLE pattern has more than {} live cells", MAX_PATTERN_ALIVE));
+                    }
+                    for _ in 0..times {
+                        alive.push(Position::new(x, y));
+                        x += 1;
+                    }
+                }
+                // A number before $ means several rows end at once (the ones in between are empty).
+                '$' => {
+                    y = advance(y, times).ok_or_else(too_big)?;
+                    x = 0;
+                }
+                '!' => break,
+                _ if c.is_whitespace() => {}
+                _ => return Err(format!("Unexpected '{}' in the RLE pattern; expected b, o, $ or !", c)),
+            }
+        }
+        Ok(Pattern::from_cells(alive, rule))
+    }
+}
+
+// The longest run an RLE file can have, like 500o. It stops a typo like
+// 99999999o from filling up the memory.
+const MAX_RUN: i32 = 10_000;
+
+// Lots of runs in a row can still add up, so the whole pattern is limited too.
+const MAX_PATTERN_SIDE: i32 = 100_000;
+const MAX_PATTERN_ALIVE: usize = 1_000_000;
+
+// Adds a run to x or y, or None if that goes past MAX_PATTERN_SIDE.
+fn advance(position: i32, times: i32) -> Option<i32> {
+    position.checked_add(times).filter(|&end| end <= MAX_PATTERN_SIDE)
+}
+
+// A few well-known patterns, in plaintext format.
+pub const BUILT_IN_PATTERNS: [(&str, &str); 6] = [
+    // Still lifes never change.
+    ("block", "OO\nOO"),
+    ("beehive", ".OO.\nO..O\n.OO."),
+    // Oscillators repeat: the blinker and toad every 2 generations, the pulsar every 3.
+    ("blinker", "OOO"),
+    ("toad", ".OOO\nOOO."),
+    ("pulsar", "..OOO...OOO..\n\nO....O.O....O\nO....O.O....O\nO....O.O....O\n..OOO...OOO..\n\n..OOO...OOO..\nO....O.O....O\nO....O.O....O\nO....O.O....O\n\n..OOO...OOO.."),
+    // A spaceship: it moves one cell diagonally every 4 generations.
+    ("glider", ".O.\n..O\nOOO"),
+];
+
+pub fn built_in_pattern(name: &str) -> Option<Pattern> {
+    BUILT_IN_PATTERNS
+        .iter()
+        .find(|(pattern, _)| pattern.eq_ignore_ascii_case(name))
+        .and_then(|(_, text)| Pattern::from_plaintext(text).ok())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct World {
+    cells: Grid<bool>,
+    rule: Rule,
+    generation: u64,
+}
+
+impl World {
+    // An empty world. Edges::Wrapping makes it toroidal.
+    pub fn new(width: usize, height: usize, rule: Rule, edges: Edges) -> Result<World, String> {
+        Ok(World {
+            cells: Grid::new(width, height, false, edges)?,
+            rule,
+            generation: 0,
+        })
+    }
+
+    // A world with the pattern in the middle and `margin` empty cells around it.
+    pub fn around(pattern: &Pattern, margin: usize, rule: Rule, edges: Edges) -> Result<World, String> {
+        let mut world = World::new(pattern.width + 2 * margin, pattern.height + 2 * margin, rule, edges)?;
+        world.place(pattern, Position::new(margin as i32, margin as i32))?;
+        Ok(world)
+    }
+
+    // Copies a pattern's live cells in, with its top left corner at `corner`.
+    pub fn place(&mut self, pattern: &Pattern, corner: Position) -> Result<(), String> {
+        for cell in &pattern.alive {
+            let position = Position::new(corner.x + cell.x, corner.y + cell.y);
+            self.cells
+                .set(position, true)
+                .map_err(|_| format!("The pattern doesn't fit in the {}x{} world", self.width(), self.height()))?;
+        }
+        Ok(())
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.height()
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn is_alive(&self, position: Position) -> bool {
+        self.cells.get(position) == Some(&true)
+    }
+
+    #[allow(dead_code)]
+    pub fn set_alive(&mut self, position: Position, alive: bool) -> Result<(), String> {
+        self.cells.set(position, alive)
+    }
+
+    // How many cells are alive.
+    pub fn population(&self) -> usize {
+        self.cells.rows().flatten().filter(|&&alive| alive).count()
+    }
+
+    // The live cells, row by row.
+    #[allow(dead_code)]
+    pub fn alive_cells(&self) -> Vec<Position> {
+        let mut alive = Vec::new();
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                if self.is_alive(Position::new(x, y)) {
+                    alive.push(Position::new(x, y));
+                }
+            }
+        }
+        alive
+    }
+
+    fn live_neighbours(&self, position: Position) -> usize {
+        self.cells
+            .neighbours(position, true)
+            .into_iter()
+            .filter(|&p| self.is_alive(p))
+            .count()
+    }
+
+    // Moves on one generation. Every cell has to look at the old generation,
+    // so the new one is built in a separate grid and then swapped in.
+    pub fn step(&mut self) {
+        let mut next = self.cells.clone();
+        for y in 0..self.height() as i32 {
+            for x in 0..self.width() as i32 {
+                let position = Position::new(x, y);
+                if let Some(cell) = next.get_mut(position) {
+                    *cell = self.rule.next_state(self.is_alive(position), self.live_neighbours(position));
+                }
+            }
+        }
+        self.cells = next;
+        self.generation += 1;
+    }
+
+    #[allow(dead_code)]
+    pub fn run(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+}
+
+// # for a live cell, . for a dead one.
+impl fmt::Display for World {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.rows() {
+            let line: String = row.iter().map(|&alive| if alive { '#' } else { '.' }).collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs a built-in pattern and checks which generations look like the first one.
+    fn check_period(name: &str, period: u64) {
+        let pattern = built_in_pattern(name).unwrap();
+        let mut world = World::around(&pattern, 3, Rule::conway(), Edges::Bounded).unwrap();
+        let start = world.alive_cells();
+        for generation in 1..=period {
+            world.step();
+            let same = world.alive_cells() == start;
+            assert_eq!(same, generation == period, "{} at generation {}", name, generation);
+        }
+    }
+
+    #[test]
+    fn still_lifes_never_change() {
+        check_period("block", 1);
+        check_period("beehive", 1);
+    }
+
+    #[test]
+    fn oscillators_come_back() {
+        check_period("blinker", 2);
+        check_period("toad", 2);
+        check_period("pulsar", 3);
+    }
+
+    #[test]
+    fn glider_moves_diagonally() {
+        let pattern = built_in_pattern("glider").unwrap();
+        let mut world = World::around(&pattern, 5, Rule::conway(), Edges::Bounded).unwrap();
+        let start = world.alive_cells();
+        world.run(4);
+        let moved: Vec<Position> = start.iter().map(|p| Position::new(p.x + 1, p.y + 1)).collect();
+        assert_eq!(world.alive_cells(), moved);
+        assert_eq!(world.generation(), 4);
+    }
+
+    #[test]
+    fn rle_patterns_are_read() {
+        let pattern = Pattern::from_rle("#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!").unwrap();
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(pattern.alive.len(), 5);
+    }
+
+    #[test]
+    fn huge_rle_patterns_are_an_error() {
+        let rows = format!("x = 1, y = 1\n{}o!", "10000$".repeat(215_000));
+        assert!(Pattern::from_rle(&rows).unwrap_err().contains("wide or tall"));
+        let columns = format!("x = 1, y = 1\n{}o!", "10000b".repeat(215_000));
+        assert!(Pattern::from_rle(&columns).unwrap_err().contains("wide or tall"));
+        let alive = format!("x = 1, y = 1\n{}!", "10000o$".repeat(200));
+        assert_eq!(Pattern::from_rle(&alive).unwrap_err(), "The RLE pattern has more than 1000000 live cells");
+    }
+}
@@ -0,0 +1,364 @@
+// Making and solving mazes on a grid.
+//
+// A maze here is a grid of cells with walls between them. Every maze made by
+// this module is "perfect": there is exactly one route between any two cells.
+//
+// Making a maze (both start with every wall up and knock some down):
+// - Recursive backtracker: wander randomly, knocking down walls into cells you
+//   haven't been to, and go back the way you came when you get stuck. Makes
+//   long, twisty corridors.
+// - Kruskal: go through every wall in a random order, and knock it down if the
+//   cells on each side aren't joined up yet. Makes lots of short dead ends.
+//
+// Solving a maze gives the route as a Vec<Direction>:
+// - BFS (breadth-first search) tries every route one step at a time, so the
+//   first route it finds is the shortest.
+// - DFS (depth-first search) follows one corridor as far as it goes before
+//   trying another.
+// - A* (A-star) is like BFS, but tries the cells closest to the goal first.
+//
+// The random numbers come from the rand crate, like random_crate.rs.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt;
+
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::Rng;
+
+use crate::utils::grid::{Edges, Grid, Position};
+use crate::Direction;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    Backtracker,
+    Kruskal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Solver {
+    Bfs,
+    Dfs,
+    AStar,
+}
+
+impl fmt::Display for Solver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Solver::Bfs => "BFS",
+            Solver::Dfs => "DFS",
+            Solver::AStar => "A*",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A route through the maze, and how many cells the solver looked at to find it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub route: Vec<Direction>,
+    pub explored: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Maze {
+    // For each cell, the directions you can move in without hitting a wall.
+    open: Grid<Vec<Direction>>,
+}
+
+impl Maze {
+    // A maze with every wall up.
+    pub fn closed(width: usize, height: usize) -> Result<Maze, String> {
+        Ok(Maze {
+            open: Grid::new(width, height, Vec::new(), Edges::Bounded)?,
+        })
+    }
+
+    pub fn generate<R: Rng + ?Sized>(generator: Generator, width: usize, height: usize, rng: &mut R) -> Result<Maze, String> {
+        match generator {
+            Generator::Backtracker => Maze::backtracker(width, height, rng),
+            Generator::Kruskal => Maze::kruskal(width, height, rng),
+        }
+    }
+
+    pub fn backtracker<R: Rng + ?Sized>(width: usize, height: usize, rng: &mut R) -> Result<Maze, String> {
+        let mut maze = Maze::closed(width, height)?;
+        let mut visited = Grid::new(width, height, false, Edges::Bounded)?;
+        let start = Position::new(0, 0);
+        visited.set(start, true)?;
+
+        // The cells we came through to get here. It's "recursive" because each
+        // step is like calling the same function again from the new cell, but
+        // a Vec used as a stack does the same job without running out of stack
+        // space on a big maze.
+        let mut stack = vec![start];
+        while let Some(&current) = stack.last() {
+            let unvisited: Vec<Direction> = Direction::STRAIGHT
+                .iter()
+                .copied()
+                .filter(|&d| maze.open.step(current, d).is_some_and(|next| visited.get(next) == Some(&false)))
+                .collect();
+            match unvisited.choose(rng) {
+                Some(&direction) => {
+                    let next = maze.carve(current, direction)?;
+                    visited.set(next, true)?;
+                    stack.push(next);
+                }
+                // Stuck, so go back one cell.
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+        Ok(maze)
+    }
+
+    pub fn kruskal<R: Rng + ?Sized>(width: usize, height: usize, rng: &mut R) -> Result<Maze, String> {
+        let mut maze = Maze::closed(width, height)?;
+
+        // Every wall, as the cell on its left or top and the direction to the other side.
+        let mut walls: Vec<(Position, Direction)> = Vec::new();
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                walls.push((Position::new(x, y), Direction::Right));
+                walls.push((Position::new(x, y), Direction::Down));
+            }
+        }
+        walls.shuffle(rng);
+
+        // Each cell starts in its own group. Knocking down a wall joins two groups.
+        let mut groups = Groups::new(width * height);
+        let number = |p: Position| p.y as usize * width + p.x as usize;
+        for (cell, direction) in walls {
+            let Some(other) = maze.open.step(cell, direction) else {
+                // A wall on the outside edge.
+                continue;
+            };
+            if groups.join(number(cell), number(other)) {
+                maze.carve(cell, direction)?;
+            }
+        }
+        Ok(maze)
+    }
+
+    pub fn width(&self) -> usize {
+        self.open.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.open.height()
+    }
+
+    // The top left corner, where solving starts by default.
+    pub fn entrance(&self) -> Position {
+        Position::new(0, 0)
+    }
+
+    // The bottom right corner.
+    pub fn exit(&self) -> Position {
+        Position::new(self.width() as i32 - 1, self.height() as i32 - 1)
+    }
+
+    pub fn can_move(&self, from: Position, direction: Direction) -> bool {
+        self.open.get(from).is_some_and(|open| open.contains(&direction))
+    }
+
+    // Knocks down the wall on one side of a cell, and returns the cell on the other side.
+    fn carve(&mut self, from: Position, direction: Direction) -> Result<Position, String> {
+        let to = self
+            .open
+            .step(from, direction)
+            .ok_or_else(|| format!("Can't knock down the outside wall at {}", from))?;
+        if let Some(open) = self.open.get_mut(from) {
+            open.push(direction);
+        }
+        if let Some(open) = self.open.get_mut(to) {
+            open.push(direction.opposite());
+        }
+        Ok(to)
+    }
+
+    // The cells you can move to from `from`, and the direction to each one.
+    fn moves_from(&self, from: Position) -> Vec<(Direction, Position)> {
+        Direction::STRAIGHT
+            .iter()
+            .filter(|&&d| self.can_move(from, d))
+            .filter_map(|&d| Some((d, self.open.step(from, d)?)))
+            .collect()
+    }
+
+    // Finds a route from one cell to another. None means there isn't one
+    // (which can't happen in a perfect maze, but can in a closed one).
+    pub fn solve(&self, from: Position, to: Position, solver: Solver) -> Option<Solution> {
+        if !self.open.contains(from) || !self.open.contains(to) {
+            return None;
+        }
+        // For each cell reached, the cell we came from and the direction we moved.
+        let mut came_from: HashMap<Position, (Position, Direction)> = HashMap::new();
+        let explored = match solver {
+            Solver::Bfs => self.breadth_first(from, to, &mut came_from),
+            Solver::Dfs => self.depth_first(from, to, &mut came_from),
+            Solver::AStar => self.a_star(from, to, &mut came_from),
+        }?;
+
+        // Follow came_from back from the goal, then turn the route round.
+        let mut route = Vec::new();
+        let mut current = to;
+        while current != from {
+            let (previous, direction) = came_from[&current];
+            route.push(direction);
+            current = previous;
+        }
+        route.reverse();
+        Some(Solution { route, explored })
+    }
+
+    // These three fill in came_from and return how many cells they looked at,
+    // or None if they never reached the goal.
+
+    fn breadth_first(&self, from: Position, to: Position, came_from: &mut HashMap<Position, (Position, Direction)>) -> Option<usize> {
+        // A VecDeque is a queue: push_back adds to the end, pop_front takes from the front.
+        let mut queue = VecDeque::from([from]);
+        let mut explored = 0;
+        while let Some(current) = queue.pop_front() {
+            explored += 1;
+            if current == to {
+                return Some(explored);
+            }
+            for (direction, next) in self.moves_from(current) {
+                if next != from && !came_from.contains_key(&next) {
+                    came_from.insert(next, (current, direction));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    fn depth_first(&self, from: Position, to: Position, came_from: &mut HashMap<Position, (Position, Direction)>) -> Option<usize> {
+        // The same as breadth_first, but with a stack (last in, first out) instead of a queue.
+        let mut stack = vec![from];
+        let mut explored = 0;
+        while let Some(current) = stack.pop() {
+            explored += 1;
+            if current == to {
+                return Some(explored);
+            }
+            for (direction, next) in self.moves_from(current) {
+                if next != from && !came_from.contains_key(&next) {
+                    came_from.insert(next, (current, direction));
+                    stack.push(next);
+                }
+            }
+        }
+        None
+    }
+
+    fn a_star(&self, from: Position, to: Position, came_from: &mut HashMap<Position, (Position, Direction)>) -> Option<usize> {
+        // A BinaryHeap always pops its biggest item. Wrapping the items in Reverse
+        // makes it pop the smallest instead: the cell with the lowest
+        // (steps so far + straight-line guess of the steps left).
+        let mut heap = BinaryHeap::from([Reverse((from.manhattan_distance(to), 0, from.x, from.y))]);
+        let mut steps_to: HashMap<Position, u32> = HashMap::from([(from, 0)]);
+        let mut explored = 0;
+        while let Some(Reverse((_, steps, x, y))) = heap.pop() {
+            let current = Position::new(x, y);
+            // A cell can be in the heap more than once. Skip the older, longer ways there.
+            if steps > steps_to[&current] {
+                continue;
+            }
+            explored += 1;
+            if current == to {
+                return Some(explored);
+            }
+            for (direction, next) in self.moves_from(current) {
+                let next_steps = steps + 1;
+                if steps_to.get(&next).is_none_or(|&best| next_steps < best) {
+                    steps_to.insert(next, next_steps);
+                    came_from.insert(next, (current, direction));
+                    heap.push(Reverse((next_steps + next.manhattan_distance(to), next_steps, next.x, next.y)));
+                }
+            }
+        }
+        None
+    }
+
+    // Draws the maze with + - | for the walls. If a route is given, it is
+    // drawn with arrows from the entrance, with S at the start and E at the end.
+    pub fn render(&self, route: Option<&[Direction]>) -> String {
+        // The character in the middle of each cell.
+        let mut marks = Grid::new(self.width(), self.height(), ' ', Edges::Bounded).expect("the maze is at least 1x1");
+        if let Some(route) = route
+            && let Ok(path) = self.open.walk(self.entrance(), route)
+        {
+            marks.draw_path(&path, route);
+        }
+
+        let mut text = String::new();
+        text.push_str(&"+---".repeat(self.width()));
+        text.push_str("+\n");
+        for y in 0..self.height() as i32 {
+            let mut cells = String::from("|");
+            let mut below = String::from("+");
+            for x in 0..self.width() as i32 {
+                let cell = Position::new(x, y);
+                let mark = marks.get(cell).copied().unwrap_or(' ');
+                cells.push_str(&format!(" {} ", mark));
+                cells.push(if self.can_move(cell, Direction::Right) { ' ' } else { '|' });
+                below.push_str(if self.can_move(cell, Direction::Down) { "   " } else { "---" });
+                below.push('+');
+            }
+            text.push_str(&cells);
+            text.push('\n');
+            text.push_str(&below);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl fmt::Display for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(None))
+    }
+}
+
+// Keeps track of which cells are joined up, for Kruskal's algorithm. This is
+// called a "union-find" or "disjoint set": each group has one cell as its
+// leader, and every cell points towards its group's leader.
+struct Groups {
+    parent: Vec<usize>,
+}
+
+impl Groups {
+    fn new(size: usize) -> Groups {
+        Groups {
+            parent: (0..size).collect(),
+        }
+    }
+
+    fn leader(&mut self, cell: usize) -> usize {
+        let mut leader = cell;
+        while self.parent[leader] != leader {
+            leader = self.parent[leader];
+        }
+        // Point everything on the way straight at the leader, so next time is quicker.
+        let mut current = cell;
+        while self.parent[current] != leader {
+            let next = self.parent[current];
+            self.parent[current] = leader;
+            current = next;
+        }
+        leader
+    }
+
+    // Joins the groups of two cells. Returns false if they were already in the same group.
+    fn join(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.leader(a), self.leader(b));
+        if a == b {
+            return false;
+        }
+        self.parent[a] = b;
+        true
+    }
+}
@@ -0,0 +1,311 @@
+// Converting numbers to English words and Roman numerals, and back again.
+//
+// The match demo in main.rs only knows the words for 1 to 4 and falls back to
+// "Unknown" for everything else. The functions in this file work for any i64.
+
+const UNITS: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen",
+    "nineteen",
+];
+
+// TENS[2] is "twenty", TENS[3] is "thirty" and so on. The first two slots are unused.
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+// Each scale word is 1000 times bigger than the one before it.
+// An i64 can hold up to about 9.2 quintillion, so we don't need anything bigger.
+const SCALES: [(&str, u64); 6] = [
+    ("quintillion", 1_000_000_000_000_000_000),
+    ("quadrillion", 1_000_000_000_000_000),
+    ("trillion", 1_000_000_000_000),
+    ("billion", 1_000_000_000),
+    ("million", 1_000_000),
+    ("thousand", 1_000),
+];
+
+// Roman numerals, including the subtractive pairs like CM (900) and IV (4),
+// from biggest to smallest.
+const ROMAN: [(&str, i64); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+// Spells a number between 1 and 999, e.g. 342 => "three hundred forty-two".
+fn below_thousand(n: u64) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    if n >= 100 {
+        parts.push(format!("{} hundred", UNITS[(n / 100) as usize]));
+    }
+
+    let rest = n % 100;
+    if rest >= 20 {
+        let tens = TENS[(rest / 10) as usize];
+        if rest.is_multiple_of(10) {
+            parts.push(tens.to_string());
+        } else {
+            parts.push(format!("{}-{}", tens, UNITS[(rest % 10) as usize]));
+        }
+    } else if rest > 0 {
+        parts.push(UNITS[rest as usize].to_string());
+    }
+
+    parts.join(" ")
+}
+
+// Spells any unsigned number by splitting it into groups of three digits.
+fn cardinal(n: u64) -> String {
+    if n == 0 {
+        return String::from("zero");
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut remaining = n;
+
+    for (word, size) in SCALES {
+        if remaining >= size {
+            parts.push(format!("{} {}", below_thousand(remaining / size), word));
+            remaining %= size;
+        }
+    }
+    if remaining > 0 {
+        parts.push(below_thousand(remaining));
+    }
+
+    parts.join(" ")
+}
+
+// Spells a number in English, e.g. 42 => "forty-two", -7 => "negative seven".
+pub fn to_words(n: i64) -> String {
+    // unsigned_abs() avoids an overflow for i64::MIN, which has no positive i64 partner.
+    let words = cardinal(n.unsigned_abs());
+    if n < 0 {
+        format!("negative {}", words)
+    } else {
+        words
+    }
+}
+
+// Turns the last word of a cardinal number into its ordinal form.
+fn ordinal_word(word: &str) -> String {
+    match word {
+        "one" => String::from("first"),
+        "two" => String::from("second"),
+        "three" => String::from("third"),
+        "five" => String::from("fifth"),
+        "eight" => String::from("eighth"),
+        "nine" => String::from("ninth"),
+        "twelve" => String::from("twelfth"),
+        // twenty => twentieth, thirty => thirtieth, ...
+        _ if word.ends_with('y') => format!("{}ieth", &word[..word.len() - 1]),
+        _ => format!("{}th", word),
+    }
+}
+
+// Spells a number as an ordinal, e.g. 42 => "forty-second", 100 => "one hundredth".
+pub fn to_ordinal_words(n: i64) -> String {
+    let words = to_words(n);
+
+    // Only the very last word changes: "forty-two" => "forty-second".
+    // rfind looks for the last space or hyphen, whichever comes later.
+    let split_at = words.rfind([' ', '-']).map(|i| i + 1).unwrap_or(0);
+    let (start, last) = words.split_at(split_at);
+    format!("{}{}", start, ordinal_word(last))
+}
+
+// Works out the cardinal word for an ordinal word, e.g. "second" => "two".
+fn cardinal_from_ordinal(word: &str) -> Option<String> {
+    let cardinal = match word {
+        "first" => String::from("one"),
+        "second" => String::from("two"),
+        "third" => String::from("three"),
+        "fifth" => String::from("five"),
+        "eighth" => String::from("eight"),
+        "ninth" => String::from("nine"),
+        "twelfth" => String::from("twelve"),
+        _ if word.ends_with("ieth") => format!("{}y", &word[..word.len() - 4]),
+        _ if word.ends_with("th") => word[..word.len() - 2].to_string(),
+        _ => return None,
+    };
+    Some(cardinal)
+}
+
+// What kind of word we read last. This lets from_words reject things like
+// "two three" or "hundred five" that aren't real English numbers.
+#[derive(PartialEq)]
+enum Previous {
+    Start,
+    Unit,
+    Teen,
+    Tens,
+    Hundred,
+    Scale,
+}
+
+// Reads a number written in English, e.g. "forty-two" => 42.
+// Ordinals ("forty-second"), "and" ("one hundred and five") and a leading
+// "negative" or "minus" are understood too.
+pub fn from_words(text: &str) -> Result<i64, String> {
+    let lowered = text.trim().to_lowercase();
+    let mut words: Vec<String> = lowered
+        .split(|c: char| c.is_whitespace() || c == '-' || c == ',')
+        .filter(|w| !w.is_empty() && *w != "and")
+        .map(|w| w.to_string())
+        .collect();
+
+    if words.is_empty() {
+        return Err(String::from("No number words found"));
+    }
+
+    let negative = words[0] == "negative" || words[0] == "minus";
+    if negative {
+        words.remove(0);
+        if words.is_empty() {
+            return Err(format!("'{}' has a sign but no number", text));
+        }
+    }
+
+    // Only the last word can be an ordinal, so turn it back into a cardinal first.
+    let last = words.len() - 1;
+    let is_known = |w: &str| {
+        UNITS.contains(&w) || TENS.contains(&w) || w == "hundred" || SCALES.iter().any(|(s, _)| *s == w)
+    };
+    if !is_known(&words[last])
+        && let Some(cardinal) = cardinal_from_ordinal(&words[last])
+        && is_known(&cardinal)
+    {
+        words[last] = cardinal;
+    }
+
+    if words.len() == 1 && words[0] == "zero" {
+        return Ok(0);
+    }
+
+    // i128 has room for anything an i64 can hold, so we can add first
+    // and check whether the answer fits at the end.
+    let mut total: i128 = 0;
+    let mut group: u64 = 0;
+    let mut last_scale = u64::MAX;
+    let mut previous = Previous::Start;
+
+    for word in &words {
+        if let Some(value) = UNITS.iter().position(|u| u == word) {
+            let value = value as u64;
+            let allowed = match previous {
+                Previous::Start | Previous::Hundred | Previous::Scale => value > 0,
+                Previous::Tens => (1..10).contains(&value),
+                Previous::Unit | Previous::Teen => false,
+            };
+            if !allowed {
+                return Err(format!("'{}' can't be used there in '{}'", word, text));
+            }
+            group += value;
+            previous = if value < 10 { Previous::Unit } else { Previous::Teen };
+        } else if let Some(value) = TENS.iter().position(|t| !t.is_empty() && t == word) {
+            if !matches!(previous, Previous::Start | Previous::Hundred | Previous::Scale) {
+                return Err(format!("'{}' can't be used there in '{}'", word, text));
+            }
+            group += value as u64 * 10;
+            previous = Previous::Tens;
+        } else if word == "hundred" {
+            if previous != Previous::Unit || group >= 10 {
+                return Err(format!("'hundred' must follow a single digit in '{}'", text));
+            }
+            group *= 100;
+            previous = Previous::Hundred;
+        } else if let Some((_, size)) = SCALES.iter().find(|(s, _)| s == word) {
+            if group == 0 || *size >= last_scale {
+                return Err(format!("'{}' can't be used there in '{}'", word, text));
+            }
+            total += group as i128 * *size as i128;
+            group = 0;
+            last_scale = *size;
+            previous = Previous::Scale;
+        } else {
+            return Err(format!("'{}' is not a number word", word));
+        }
+    }
+
+    total += group as i128;
+    if negative {
+        total = -total;
+    }
+
+    i64::try_from(total).map_err(|_| format!("'{}' is too big to fit in an i64", text))
+}
+
+// Converts a number to Roman numerals, e.g. 1994 => "MCMXCIV".
+// Roman numerals have no zero or negatives, and the standard form stops at 3999.
+pub fn to_roman(n: i64) -> Result<String, String> {
+    if !(1..=3999).contains(&n) {
+        return Err(format!("Roman numerals can only show 1 to 3999, not {}", n));
+    }
+
+    let mut remaining = n;
+    let mut roman = String::new();
+    for (symbol, value) in ROMAN {
+        while remaining >= value {
+            roman.push_str(symbol);
+            remaining -= value;
+        }
+    }
+    Ok(roman)
+}
+
+// Reads a Roman numeral, e.g. "MCMXCIV" => 1994.
+// Badly formed numerals like "IIII" or "IC" are rejected rather than guessed at.
+pub fn from_roman(text: &str) -> Result<i64, String> {
+    let upper = text.trim().to_uppercase();
+    if upper.is_empty() {
+        return Err(String::from("An empty string is not a Roman numeral"));
+    }
+
+    let mut values: Vec<i64> = Vec::new();
+    for c in upper.chars() {
+        let value = match c {
+            'I' => 1,
+            'V' => 5,
+            'X' => 10,
+            'L' => 50,
+            'C' => 100,
+            'D' => 500,
+            'M' => 1000,
+            _ => return Err(format!("'{}' is not a Roman numeral letter", c)),
+        };
+        values.push(value);
+    }
+
+    // A smaller letter before a bigger one is subtracted (IV = 4),
+    // otherwise it is added (VI = 6).
+    let mut total = 0;
+    for (i, value) in values.iter().enumerate() {
+        match values.get(i + 1) {
+            Some(next) if next > value => total -= value,
+            _ => total += value,
+        }
+    }
+
+    // The easiest way to check the numeral was written properly is to
+    // write the total back out and compare.
+    match to_roman(total) {
+        Ok(canonical) if canonical == upper => Ok(total),
+        Ok(canonical) => Err(format!(
+            "'{}' is not a valid Roman numeral (did you mean {}?)",
+            text, canonical
+        )),
+        Err(_) => Err(format!("'{}' is not a valid Roman numeral", text)),
+    }
+}
@@ -0,0 +1,418 @@
+// Maps that print in a predictable order, and a map with several values per key.
+//
+// A HashMap doesn't remember the order things were added, and the order
+// changes every time the program runs. That's why `scores` and `capitalCities`
+// in main.rs print differently each time. This module has:
+//
+// - OrderedMap<K, V>: works like a HashMap, but remembers insertion order.
+// - MultiMap<K, V>: each key can have several values, like a country with
+//   several cities. It keeps insertion order too.
+// - sorted_pairs(): the pairs of an ordinary HashMap, sorted by key.
+//
+// Both maps have the same insert/get/remove/entry methods as HashMap.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Index;
+use std::slice;
+
+use crate::utils::json::{JsonValue, ToJson};
+
+// The pairs of a HashMap, sorted by key, so they print in the same order every run.
+//
+//     for (country, city) in sorted_pairs(&capital_cities) { ... }
+pub fn sorted_pairs<K: Ord, V>(map: &HashMap<K, V>) -> Vec<(&K, &V)> {
+    let mut pairs: Vec<(&K, &V)> = map.iter().collect();
+    pairs.sort_by(|a, b| a.0.cmp(b.0));
+    pairs
+}
+
+#[derive(Clone)]
+pub struct OrderedMap<K, V> {
+    // The pairs in the order they were added.
+    entries: Vec<(K, V)>,
+    // Where each key is in `entries`, so lookups don't have to search the whole Vec.
+    positions: HashMap<K, usize>,
+}
+
+impl<K: Clone + Eq + Hash, V> OrderedMap<K, V> {
+    pub fn new() -> OrderedMap<K, V> {
+        OrderedMap {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Adds a key and value, and returns the old value if the key was already
+    // there. A key that's already there keeps its place in the order.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.positions.get(&key) {
+            Some(&i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    // Borrow lets an OrderedMap<String, _> be searched with a &str, like a HashMap can.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let &i = self.positions.get(key)?;
+        Some(&self.entries[i].1)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let &i = self.positions.get(key)?;
+        Some(&mut self.entries[i].1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.positions.contains_key(key)
+    }
+
+    // Removes a key and returns its value. The other keys stay in the same order,
+    // which means moving everything after it down by one.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let i = self.positions.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for (later, _) in &self.entries[i..] {
+            if let Some(position) = self.positions.get_mut(later.borrow()) {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    // Gets a key's place in the map, so you can insert or change its value in one step:
+    //
+    //     *map.entry("Alice").or_insert(0) += 10;
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        Entry { map: self, key }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    // Puts the keys in sorted order. New keys still go on the end.
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
+    {
+        self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (i, (key, _)) in self.entries.iter().enumerate() {
+            self.positions.insert(key.clone(), i);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.positions.clear();
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        OrderedMap::new()
+    }
+}
+
+// Two maps are equal if they have the same pairs in the same order.
+impl<K: PartialEq, V: PartialEq> PartialEq for OrderedMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+// Prints like a HashMap, {"England": "London", "Germany": "Berlin"}, but always in the same order.
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for OrderedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.entries.iter().map(|(k, v)| (k, v))).finish()
+    }
+}
+
+// map["Alice"] works, and panics if the key isn't there, the same as a HashMap.
+impl<K, Q, V> Index<&Q> for OrderedMap<K, V>
+where
+    K: Clone + Eq + Hash + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in OrderedMap")
+    }
+}
+
+// Goes through the pairs in insertion order, as (&key, &value) like a HashMap does.
+pub struct Iter<'a, K, V> {
+    inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+}
+
+impl<'a, K: Clone + Eq + Hash, V> IntoIterator for &'a OrderedMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> FromIterator<(K, V)> for OrderedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = OrderedMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+// Unlike a HashMap, the JSON keeps the keys in the map's own order.
+impl<K: AsRef<str>, V: ToJson> ToJson for OrderedMap<K, V> {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(
+            self.entries
+                .iter()
+                .map(|(k, v)| (k.as_ref().to_string(), v.to_json()))
+                .collect(),
+        )
+    }
+}
+
+// A key's place in an OrderedMap, returned by entry().
+pub struct Entry<'a, K, V> {
+    map: &'a mut OrderedMap<K, V>,
+    key: K,
+}
+
+impl<'a, K: Clone + Eq + Hash, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    // The value for the key, inserting `default` first if the key isn't there.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    // Like or_insert, but the closure only runs if the key isn't there.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, make: F) -> &'a mut V {
+        let i = match self.map.positions.get(&self.key) {
+            Some(&i) => i,
+            None => {
+                self.map.insert(self.key, make());
+                self.map.entries.len() - 1
+            }
+        };
+        &mut self.map.entries[i].1
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    // Changes the value if the key is there. Returns the entry so or_insert can follow.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, change: F) -> Self {
+        if let Some(value) = self.map.get_mut(&self.key) {
+            change(value);
+        }
+        self
+    }
+}
+
+// A map where each key has a list of values, in the order they were added.
+//
+//     cities.insert("England", "London");
+//     cities.insert("England", "Manchester");
+//     cities.get("England") => Some(["London", "Manchester"])
+#[derive(Clone, PartialEq)]
+pub struct MultiMap<K, V> {
+    map: OrderedMap<K, Vec<V>>,
+}
+
+impl<K: Clone + Eq + Hash, V> MultiMap<K, V> {
+    pub fn new() -> MultiMap<K, V> {
+        MultiMap { map: OrderedMap::new() }
+    }
+
+    // The number of keys.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    // The number of values, counting every value of every key.
+    #[allow(dead_code)]
+    pub fn total_values(&self) -> usize {
+        self.map.values().map(|values| values.len()).sum()
+    }
+
+    // Adds a value to the end of the key's list. Unlike a HashMap, nothing is replaced.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.map.entry(key).or_default().push(value);
+    }
+
+    // All the values for a key. A key that's in the map always has at least one.
+    pub fn get<Q>(&self, key: &Q) -> Option<&[V]>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(key).map(|values| values.as_slice())
+    }
+
+    // The first value added for a key.
+    #[allow(dead_code)]
+    pub fn get_first<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(key)?.first()
+    }
+
+    #[allow(dead_code)]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    // Removes a key and all its values.
+    #[allow(dead_code)]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<Vec<V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove(key)
+    }
+
+    // Removes one value from a key's list. The key goes too once its list is empty.
+    // Returns false if the value wasn't there.
+    pub fn remove_value<Q>(&mut self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: PartialEq,
+    {
+        let Some(values) = self.map.get_mut(key) else {
+            return false;
+        };
+        let Some(i) = values.iter().position(|v| v == value) else {
+            return false;
+        };
+        values.remove(i);
+        if values.is_empty() {
+            self.map.remove(key);
+        }
+        true
+    }
+
+    // The key's list of values, to change it directly:
+    //
+    //     cities.entry("Norway").or_default().push("Bergen");
+    //
+    // If you empty the list this way, the key stays in the map with no values.
+    #[allow(dead_code)]
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, Vec<V>> {
+        self.map.entry(key)
+    }
+
+    // Each key with all its values.
+    #[allow(dead_code)]
+    pub fn iter_all(&self) -> Iter<'_, K, Vec<V>> {
+        self.map.iter()
+    }
+
+    // Every (key, value) pair, so a key with three values comes up three times.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map
+            .iter()
+            .flat_map(|(key, values)| values.iter().map(move |value| (key, value)))
+    }
+
+    #[allow(dead_code)]
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.map.keys()
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Default for MultiMap<K, V> {
+    fn default() -> Self {
+        MultiMap::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for MultiMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> FromIterator<(K, V)> for MultiMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = MultiMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
@@ -0,0 +1,253 @@
+// A registry of people: the Person struct from main.rs, kept in a list with
+// an id for each one.
+//
+// main.rs makes one Person and changes their age. Here there can be any
+// number of people. Each gets a number (its id) when it's added, and the
+// id never changes or gets reused, even after the person is deleted. Two
+// people can have the same name, so the id is how you say which one you mean.
+//
+// The registry is saved as JSON, like the shopping lists.
+
+use std::fs;
+use std::ops::RangeInclusive;
+
+use crate::utils::json::{self, FromJson, JsonValue, ToJson};
+use crate::utils::validation::{self, Validate, Validator, Violation};
+use crate::Person;
+
+// Nobody has lived longer than about 122 years, so 150 leaves plenty of room.
+pub const MAX_AGE: u32 = 150;
+const MAX_NAME_LENGTH: usize = 100;
+
+// The rules every Person has to follow. Building a Person with { } doesn't
+// check anything, so call validate() on one that came from outside.
+impl Validate for Person {
+    fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut v = Validator::new();
+        v.text("name", &self.name)
+            .not_empty()
+            .length(1..=MAX_NAME_LENGTH)
+            .custom(|name| {
+                if name.trim() != name {
+                    return Err(String::from("can't start or end with spaces"));
+                }
+                Ok(())
+            });
+        v.number("age", self.age).range(0..=MAX_AGE);
+        v.finish()
+    }
+}
+
+// Checks a name and an age, and returns the name with spaces trimmed off.
+pub fn check_person(name: &str, age: u32) -> Result<String, String> {
+    let person = Person {
+        name: name.trim().to_string(),
+        age,
+    };
+    person.validate().map_err(|violations| validation::describe(&violations))?;
+    Ok(person.name)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub id: u32,
+    pub person: Person,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PersonField {
+    Id,
+    Name,
+    Age,
+}
+
+impl std::str::FromStr for PersonField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "id" => Ok(PersonField::Id),
+            "name" => Ok(PersonField::Name),
+            "age" => Ok(PersonField::Age),
+            _ => Err(format!("'{}' isn't a field; use id, name or age", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonRegistry {
+    records: Vec<Record>,
+    // The id the next person will get.
+    next_id: u32,
+}
+
+impl Default for PersonRegistry {
+    fn default() -> Self {
+        PersonRegistry::new()
+    }
+}
+
+impl PersonRegistry {
+    pub fn new() -> PersonRegistry {
+        PersonRegistry {
+            records: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    // Everyone, in the order they were added.
+    #[allow(dead_code)]
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    // Adds a person and returns their id.
+    pub fn add(&mut self, name: &str, age: u32) -> Result<u32, String> {
+        let name = check_person(name, age)?;
+        let id = self.next_id;
+        self.next_id = self
+            .next_id
+            .checked_add(1)
+            .ok_or_else(|| String::from("The registry has run out of ids"))?;
+        self.records.push(Record { id, person: Person { name, age } });
+        Ok(id)
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Person> {
+        self.records.iter().find(|r| r.id == id).map(|r| &r.person)
+    }
+
+    fn position(&self, id: u32) -> Result<usize, String> {
+        self.records
+            .iter()
+            .position(|r| r.id == id)
+            .ok_or_else(|| format!("There is no person with id {}", id))
+    }
+
+    // Changes a person's name, age, or both. None leaves that field as it is.
+    // Nothing changes unless the new values are both valid.
+    pub fn update(&mut self, id: u32, name: Option<&str>, age: Option<u32>) -> Result<(), String> {
+        let index = self.position(id)?;
+        let person = &self.records[index].person;
+        let name = check_person(name.unwrap_or(&person.name), age.unwrap_or(person.age))?;
+        let person = &mut self.records[index].person;
+        person.name = name;
+        if let Some(age) = age {
+            person.age = age;
+        }
+        Ok(())
+    }
+
+    // Removes a person and gives them back.
+    pub fn delete(&mut self, id: u32) -> Result<Person, String> {
+        let index = self.position(id)?;
+        Ok(self.records.remove(index).person)
+    }
+
+    // Everyone with this name, ignoring upper and lower case.
+    pub fn find_by_name(&self, name: &str) -> Vec<&Record> {
+        let name = name.trim().to_lowercase();
+        self.records.iter().filter(|r| r.person.name.to_lowercase() == name).collect()
+    }
+
+    // Everyone whose name has `text` in it, ignoring upper and lower case.
+    pub fn search(&self, text: &str) -> Vec<&Record> {
+        let text = text.trim().to_lowercase();
+        self.records.iter().filter(|r| r.person.name.to_lowercase().contains(&text)).collect()
+    }
+
+    // Everyone aged from the start of the range to the end, like 18..=65.
+    pub fn age_range(&self, ages: RangeInclusive<u32>) -> Vec<&Record> {
+        self.records.iter().filter(|r| ages.contains(&r.person.age)).collect()
+    }
+
+    // Everyone the test returns true for, like |p| p.name.starts_with('A').
+    pub fn filter<F: Fn(&Person) -> bool>(&self, test: F) -> Vec<&Record> {
+        self.records.iter().filter(|r| test(&r.person)).collect()
+    }
+
+    // Everyone, sorted by a field. People with the same name or age stay in id order.
+    pub fn sorted(&self, by: PersonField, descending: bool) -> Vec<&Record> {
+        let mut sorted: Vec<&Record> = self.records.iter().collect();
+        let compare = |a: &Record, b: &Record| match by {
+            PersonField::Id => a.id.cmp(&b.id),
+            PersonField::Name => a.person.name.to_lowercase().cmp(&b.person.name.to_lowercase()),
+            PersonField::Age => a.person.age.cmp(&b.person.age),
+        };
+        // sort_by is "stable": equal items keep their order, which is id order here.
+        // Swapping a and b sorts the other way without changing that.
+        sorted.sort_by(|a, b| if descending { compare(b, a) } else { compare(a, b) });
+        sorted
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_json().pretty()).map_err(|e| format!("Could not write {}: {}", path, e))
+    }
+
+    // Loads the registry. A missing file gives an empty one.
+    pub fn load(path: &str) -> Result<PersonRegistry, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(PersonRegistry::new()),
+            Err(e) => return Err(format!("Could not read {}: {}", path, e)),
+        };
+        let value = json::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+        PersonRegistry::from_json(&value).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+impl ToJson for Record {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            (String::from("id"), self.id.to_json()),
+            (String::from("name"), self.person.name.to_json()),
+            (String::from("age"), self.person.age.to_json()),
+        ])
+    }
+}
+
+impl FromJson for Record {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        Ok(Record {
+            id: u32::from_json(value.field("id")?).map_err(|e| format!("id: {}", e))?,
+            person: Person::from_json(value)?,
+        })
+    }
+}
+
+impl ToJson for PersonRegistry {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            (String::from("next_id"), self.next_id.to_json()),
+            (String::from("people"), self.records.to_json()),
+        ])
+    }
+}
+
+impl FromJson for PersonRegistry {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        let records: Vec<Record> = Vec::from_json(value.field("people")?).map_err(|e| format!("people: {}", e))?;
+        for (i, record) in records.iter().enumerate() {
+            check_person(&record.person.name, record.person.age).map_err(|e| format!("people[{}]: {}", i, e))?;
+            if records[..i].iter().any(|r| r.id == record.id) {
+                return Err(format!("people[{}]: the id {} is used twice", i, record.id));
+            }
+        }
+        // A hand-edited file might have a next_id that's too small, so make
+        // sure it's past every id in use.
+        let highest = records.iter().map(|r| r.id).max().unwrap_or(0);
+        let next_id = u32::from_json(value.field("next_id")?).map_err(|e| format!("next_id: {}", e))?;
+        Ok(PersonRegistry {
+            records,
+            next_id: next_id.max(highest.saturating_add(1)),
+        })
+    }
+}
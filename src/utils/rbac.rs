@@ -0,0 +1,600 @@
+// Role-based access control: deciding whether someone is allowed to do something.
+//
+// The Operators lesson in main.rs works out `logged_in && !is_admin` from two
+// bools. Real programs need more than that, so this module has:
+//
+// - Permissions: names for actions, like "grades:read". A permission ending
+//   in ":*" covers everything starting with that prefix, and "*" covers everything.
+// - Roles: a named set of permissions, like "teacher". A role can inherit
+//   from other roles and gets all of their permissions too.
+// - Rules: "allow" or "deny" an action, optionally only if a condition holds.
+//   Conditions use and/or/not (or &&, ||, !) and comparisons on attributes,
+//   which are facts about the request, like logged_in or age.
+//
+// A policy file looks like this (# starts a comment):
+//
+//   role student: grades:read
+//   role teacher inherits student: grades:write, grades:curve
+//   role admin inherits teacher: *
+//
+//   deny * if not logged_in
+//   allow grades:read if is_owner
+//   deny grades:write if has_role("teacher") && term == "closed"
+//
+// To decide, the rules are checked from top to bottom and the first one that
+// matches wins, like a firewall. If no rule matches, the action is allowed
+// if one of the roles has the permission, and denied otherwise.
+//
+// Roles must be defined before they are inherited from, which also means two
+// roles can never inherit from each other in a loop.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+
+use crate::utils::ordered_map::OrderedMap;
+use crate::utils::template::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// Whether a granted permission (or a rule's action) covers the wanted one.
+pub fn permission_matches(granted: &str, wanted: &str) -> bool {
+    match granted.strip_suffix('*') {
+        Some("") => true,
+        Some(prefix) if prefix.ends_with(':') => wanted.starts_with(prefix),
+        _ => granted == wanted,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Role {
+    pub name: String,
+    pub parents: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Effect::Allow => write!(f, "allow"),
+            Effect::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    Attribute(String),
+    HasRole(String),
+    HasPermission(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Comparison, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub line: usize,
+    pub effect: Effect,
+    pub action: String,
+    // None means the rule always applies to its action.
+    condition: Option<Expr>,
+    // The rule as written in the file, to show in a Decision.
+    pub text: String,
+}
+
+// Who is asking: their roles, and any facts the rules can check.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Subject {
+    pub roles: Vec<String>,
+    pub attributes: HashMap<String, Value>,
+}
+
+impl Subject {
+    pub fn new(roles: &[&str]) -> Subject {
+        Subject {
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: impl Into<Value>) {
+        self.attributes.insert(name.to_string(), value.into());
+    }
+}
+
+// Why a decision was made.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    Rule { line: usize, text: String },
+    Role { role: String, permission: String },
+    NothingMatched,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub reason: Reason,
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", if self.allowed { "allow" } else { "deny" })?;
+        match &self.reason {
+            Reason::Rule { line, text } => write!(f, "rule on line {} ({})", line, text),
+            Reason::Role { role, permission } => write!(f, "role {} has the permission {}", role, permission),
+            Reason::NothingMatched => write!(f, "no rule or role allows it"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AccessPolicy {
+    roles: OrderedMap<String, Role>,
+    rules: Vec<Rule>,
+}
+
+impl AccessPolicy {
+    pub fn new() -> AccessPolicy {
+        AccessPolicy::default()
+    }
+
+    pub fn load(path: &str) -> Result<AccessPolicy, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+        AccessPolicy::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<AccessPolicy, PolicyError> {
+        let mut policy = AccessPolicy::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let error = |message: String| PolicyError { line: line_number, message };
+            let code = line.split('#').next().unwrap_or("").trim();
+            let Some((keyword, rest)) = code.split_once(char::is_whitespace) else {
+                if code.is_empty() {
+                    continue;
+                }
+                return Err(error(format!("'{}' isn't a role or a rule", code)));
+            };
+            match keyword {
+                "role" => {
+                    let (head, permissions) = rest
+                        .split_once(':')
+                        .ok_or_else(|| error(String::from("A role needs a colon, like `role teacher: grades:write`")))?;
+                    let (name, parents) = match head.split_once(" inherits ") {
+                        Some((name, parents)) => (name.trim(), split_list(parents)),
+                        None => (head.trim(), Vec::new()),
+                    };
+                    let parents: Vec<&str> = parents.iter().map(|p| p.as_str()).collect();
+                    let permissions: Vec<&str> = permissions.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
+                    policy.add_role(name, &parents, &permissions).map_err(error)?;
+                }
+                "allow" | "deny" => {
+                    let effect = if keyword == "allow" { Effect::Allow } else { Effect::Deny };
+                    let rest = rest.trim();
+                    let (action, condition) = match rest.split_once(char::is_whitespace) {
+                        Some((action, condition)) => {
+                            let condition = condition.trim();
+                            let expression = condition
+                                .strip_prefix("if ")
+                                .ok_or_else(|| error(format!("Expected `if` after the action, found '{}'", condition)))?;
+                            (action, Some(parse_condition(expression).map_err(error)?))
+                        }
+                        None => (rest, None),
+                    };
+                    policy.rules.push(Rule {
+                        line: line_number,
+                        effect,
+                        action: action.to_string(),
+                        condition,
+                        text: code.to_string(),
+                    });
+                }
+                other => return Err(error(format!("'{}' isn't a role or a rule; lines start with role, allow or deny", other))),
+            }
+        }
+        Ok(policy)
+    }
+
+    // Adds a role. Its parents must already exist.
+    pub fn add_role(&mut self, name: &str, parents: &[&str], permissions: &[&str]) -> Result<(), String> {
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            return Err(format!("'{}' isn't a valid role name", name));
+        }
+        if self.roles.contains_key(name) {
+            return Err(format!("The role {} is defined twice", name));
+        }
+        if let Some(missing) = parents.iter().find(|p| !self.roles.contains_key(**p)) {
+            return Err(format!("{} inherits from {}, which isn't defined yet", name, missing));
+        }
+        self.roles.insert(
+            name.to_string(),
+            Role {
+                name: name.to_string(),
+                parents: parents.iter().map(|p| p.to_string()).collect(),
+                permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn roles(&self) -> impl Iterator<Item = &Role> {
+        self.roles.values()
+    }
+
+    #[allow(dead_code)]
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    // The roles given, plus every role they inherit from, without repeats.
+    pub fn expand_roles(&self, roles: &[String]) -> Vec<String> {
+        let mut expanded: Vec<String> = Vec::new();
+        let mut to_visit: Vec<String> = roles.to_vec();
+        while let Some(role) = to_visit.pop() {
+            if expanded.contains(&role) {
+                continue;
+            }
+            if let Some(found) = self.roles.get(&role) {
+                to_visit.extend(found.parents.iter().cloned());
+            }
+            expanded.push(role);
+        }
+        expanded
+    }
+
+    // Every permission a role has, including inherited ones.
+    #[allow(dead_code)]
+    pub fn permissions(&self, role: &str) -> Vec<&str> {
+        let mut permissions: Vec<&str> = Vec::new();
+        for name in self.expand_roles(&[role.to_string()]) {
+            if let Some(found) = self.roles.get(&name) {
+                for permission in &found.permissions {
+                    if !permissions.contains(&permission.as_str()) {
+                        permissions.push(permission);
+                    }
+                }
+            }
+        }
+        permissions
+    }
+
+    // The role and permission that grant `wanted`, if any of the roles do.
+    fn granted_by(&self, roles: &[String], wanted: &str) -> Option<(String, String)> {
+        self.expand_roles(roles).into_iter().find_map(|name| {
+            let role = self.roles.get(&name)?;
+            let permission = role.permissions.iter().find(|p| permission_matches(p, wanted))?;
+            Some((name, permission.clone()))
+        })
+    }
+
+    pub fn check(&self, subject: &Subject, action: &str) -> Decision {
+        let context = Context { policy: self, subject };
+        for rule in &self.rules {
+            let applies = permission_matches(&rule.action, action)
+                && rule.condition.as_ref().is_none_or(|condition| context.test(condition));
+            if applies {
+                return Decision {
+                    allowed: rule.effect == Effect::Allow,
+                    reason: Reason::Rule { line: rule.line, text: rule.text.clone() },
+                };
+            }
+        }
+        match self.granted_by(&subject.roles, action) {
+            Some((role, permission)) => Decision {
+                allowed: true,
+                reason: Reason::Role { role, permission },
+            },
+            None => Decision {
+                allowed: false,
+                reason: Reason::NothingMatched,
+            },
+        }
+    }
+}
+
+// Splits "a, b, c" into its parts.
+fn split_list(text: &str) -> Vec<String> {
+    text.split(',').map(|part| part.trim().to_string()).filter(|part| !part.is_empty()).collect()
+}
+
+// What a condition is checked against.
+struct Context<'a> {
+    policy: &'a AccessPolicy,
+    subject: &'a Subject,
+}
+
+impl Context<'_> {
+    // The value of an expression. None means it names an attribute the subject doesn't have.
+    fn value(&self, expr: &Expr) -> Option<Value> {
+        match expr {
+            Expr::Literal(value) => Some(value.clone()),
+            Expr::Attribute(name) => self.subject.attributes.get(name).cloned(),
+            _ => Some(Value::Bool(self.test(expr))),
+        }
+    }
+
+    // Whether a condition holds. A missing attribute counts as false, and any
+    // comparison with one is false too, so a typo in a rule makes it not
+    // match rather than match everything.
+    fn test(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Literal(_) | Expr::Attribute(_) => self.value(expr).is_some_and(|v| v.is_truthy()),
+            Expr::HasRole(role) => self.policy.expand_roles(&self.subject.roles).contains(role),
+            Expr::HasPermission(permission) => self.policy.granted_by(&self.subject.roles, permission).is_some(),
+            Expr::Not(inner) => !self.test(inner),
+            Expr::And(left, right) => self.test(left) && self.test(right),
+            Expr::Or(left, right) => self.test(left) || self.test(right),
+            Expr::Compare(left, comparison, right) => match (self.value(left), self.value(right)) {
+                (Some(left), Some(right)) => compare(&left, *comparison, &right),
+                _ => false,
+            },
+        }
+    }
+}
+
+fn compare(left: &Value, comparison: Comparison, right: &Value) -> bool {
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+        (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        // Different kinds of value are never equal, and can't be put in order.
+        _ => None,
+    };
+    match (comparison, ordering) {
+        (Comparison::NotEqual, None) => true,
+        (_, None) => false,
+        (Comparison::Equal, Some(o)) => o.is_eq(),
+        (Comparison::NotEqual, Some(o)) => o.is_ne(),
+        (Comparison::Less, Some(o)) => o.is_lt(),
+        (Comparison::LessOrEqual, Some(o)) => o.is_le(),
+        (Comparison::Greater, Some(o)) => o.is_gt(),
+        (Comparison::GreaterOrEqual, Some(o)) => o.is_ge(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(f64),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 12] = ["&&", "||", "==", "!=", "<=", ">=", "!", "<", ">", "(", ")", ","];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if c == '"' {
+            let end = rest[1..].find('"').ok_or_else(|| format!("The text {} has no closing \"", rest))?;
+            tokens.push(Token::Text(rest[1..end + 1].to_string()));
+            rest = &rest[end + 2..];
+        } else if c.is_ascii_digit() || c == '-' {
+            let end = rest[1..].find(|c: char| !c.is_ascii_digit() && c != '.').map_or(rest.len(), |i| i + 1);
+            let number = rest[..end].parse().map_err(|_| format!("'{}' isn't a number", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            return Err(format!("Unexpected '{}' in the condition", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn parse_condition(text: &str) -> Result<Expr, String> {
+    let mut parser = ConditionParser { tokens: tokenize(text)?, position: 0, depth: 0 };
+    let expr = parser.or()?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(expr),
+        Some(token) => Err(format!("Unexpected {} in the condition", describe(token))),
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("'{}'", word),
+        Token::Text(text) => format!("\"{}\"", text),
+        Token::Number(n) => n.to_string(),
+        Token::Symbol(symbol) => format!("'{}'", symbol),
+    }
+}
+
+// Reads a condition with the usual precedence: comparisons first, then not,
+// then and, then or. So `a or b and not c == 1` means `a or (b and (not (c == 1)))`.
+struct ConditionParser {
+    tokens: Vec<Token>,
+    position: usize,
+    // How deep the condition being read is nested so far.
+    depth: usize,
+}
+
+// Reading and checking a condition calls itself once per level of nesting, so
+// something like 200,000 `!` or `(` in a row would run out of stack. No real
+// policy needs anywhere near this many.
+const MAX_CONDITION_DEPTH: usize = 100;
+
+impl ConditionParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    // Moves past the next token if it is one of `options`.
+    fn accept(&mut self, options: &[Token]) -> bool {
+        let found = self.tokens.get(self.position).is_some_and(|token| options.contains(token));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Symbol(s)) if s == symbol => Ok(()),
+            Some(token) => Err(format!("Expected '{}', found {}", symbol, describe(&token))),
+            None => Err(format!("Expected '{}' but the condition ended", symbol)),
+        }
+    }
+
+    // Goes one level deeper, or fails if that's too deep. Every call has to be
+    // matched by `self.depth -= 1` once that level is read.
+    fn deeper(&mut self) -> Result<(), String> {
+        if self.depth >= MAX_CONDITION_DEPTH {
+            return Err(format!("The condition is nested more than {} deep", MAX_CONDITION_DEPTH));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // `a or b or c` is stored as `(a or b) or c`, so each `or` (and each `and`
+    // below) also counts as a level.
+    fn or(&mut self) -> Result<Expr, String> {
+        let start = self.depth;
+        let mut left = self.and()?;
+        while self.accept(&[Token::Word(String::from("or")), Token::Symbol("||")]) {
+            self.deeper()?;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        self.depth = start;
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let start = self.depth;
+        let mut left = self.not()?;
+        while self.accept(&[Token::Word(String::from("and")), Token::Symbol("&&")]) {
+            self.deeper()?;
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+        self.depth = start;
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.accept(&[Token::Word(String::from("not")), Token::Symbol("!")]) {
+            self.deeper()?;
+            let inner = self.not()?;
+            self.depth -= 1;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.atom()?;
+        let comparison = match self.tokens.get(self.position) {
+            Some(Token::Symbol("==")) => Comparison::Equal,
+            Some(Token::Symbol("!=")) => Comparison::NotEqual,
+            Some(Token::Symbol("<")) => Comparison::Less,
+            Some(Token::Symbol("<=")) => Comparison::LessOrEqual,
+            Some(Token::Symbol(">")) => Comparison::Greater,
+            Some(Token::Symbol(">=")) => Comparison::GreaterOrEqual,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        Ok(Expr::Compare(Box::new(left), comparison, Box::new(self.atom()?)))
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Symbol("(")) => {
+                self.deeper()?;
+                let inner = self.or()?;
+                self.depth -= 1;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Some(Token::Text(text)) => Ok(Expr::Literal(Value::Text(text))),
+            Some(Token::Number(n)) => Ok(Expr::Literal(Value::Number(n))),
+            Some(Token::Word(word)) => match word.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "and" | "or" | "not" => Err(format!("Expected a value before '{}'", word)),
+                "has_role" | "has_permission" => {
+                    self.expect("(")?;
+                    let Some(Token::Text(name)) = self.next() else {
+                        return Err(format!("{} needs a name in quotes, like {}(\"admin\")", word, word));
+                    };
+                    self.expect(")")?;
+                    Ok(if word == "has_role" { Expr::HasRole(name) } else { Expr::HasPermission(name) })
+                }
+                _ => Ok(Expr::Attribute(word)),
+            },
+            Some(token) => Err(format!("Expected a value, found {}", describe(&token))),
+            None => Err(String::from("The condition ended too soon")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions_nest_up_to_the_limit() {
+        let nots = "! ".repeat(MAX_CONDITION_DEPTH);
+        let policy = AccessPolicy::parse(&format!("allow grades:read if {}logged_in", nots)).unwrap();
+        let mut subject = Subject::new(&[]);
+        subject.set("logged_in", true);
+        // An even number of nots cancel out.
+        assert!(policy.check(&subject, "grades:read").allowed);
+
+        let brackets = format!("{}logged_in{}", "(".repeat(MAX_CONDITION_DEPTH), ")".repeat(MAX_CONDITION_DEPTH));
+        assert!(AccessPolicy::parse(&format!("allow grades:read if {}", brackets)).is_ok());
+    }
+
+    #[test]
+    fn deeper_conditions_are_an_error() {
+        let too_deep = [
+            "! ".repeat(200_000) + "logged_in",
+            "(".repeat(200_000) + "logged_in" + &")".repeat(200_000),
+            "logged_in".to_string() + &" or logged_in".repeat(200_000),
+            "logged_in".to_string() + &" && logged_in".repeat(200_000),
+            "(logged_in and ".repeat(60) + "x" + &")".repeat(60),
+        ];
+        for condition in too_deep {
+            let error = AccessPolicy::parse(&format!("# rules\nallow * if {}", condition)).unwrap_err();
+            assert_eq!(error.line, 2);
+            assert_eq!(error.message, format!("The condition is nested more than {} deep", MAX_CONDITION_DEPTH));
+        }
+    }
+}
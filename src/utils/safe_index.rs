@@ -0,0 +1,109 @@
+// Indexing helpers that never panic.
+//
+// arr[0] panics if the array is empty, and arr[10] panics if it has fewer than 11
+// items. The SafeIndex trait adds methods to every slice (and so to every array
+// and Vec) that return a Result instead, plus a few other ways of indexing:
+//
+//     let v = vec![10, 20, 30];
+//     v.at(-1)            => Ok(&30)  counts from the end, like Python's v[-1]
+//     v.wrapping_at(4)    => Ok(&20)  goes round again from the start
+//     v.clamped_at(99)    => Ok(&30)  stops at the last item
+//     v.sub_slice(1, 5)   => Err(...) instead of a panic
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IndexError {
+    // There is nothing to return because the slice is empty.
+    Empty,
+    // The index is past either end. Negative indexes are kept as they were given.
+    OutOfRange { index: isize, len: usize },
+    // A start..end range that doesn't fit (or goes backwards).
+    BadRange { start: usize, end: usize, len: usize },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndexError::Empty => write!(f, "the slice is empty"),
+            IndexError::OutOfRange { index, len } => {
+                write!(f, "index {} is out of range for a length of {}", index, len)
+            }
+            IndexError::BadRange { start, end, len } => {
+                write!(f, "range {}..{} doesn't fit in a length of {}", start, end, len)
+            }
+        }
+    }
+}
+
+// A trait is a set of methods that types can share. Implementing it for [T]
+// gives the methods to every slice, whatever type T is.
+pub trait SafeIndex<T> {
+    // The first item, or `default` if there isn't one.
+    fn first_or<'a>(&'a self, default: &'a T) -> &'a T;
+
+    // The item at index n, or an error if n is too big.
+    fn nth_checked(&self, n: usize) -> Result<&T, IndexError>;
+
+    // Python-style indexing: -1 is the last item, -2 the one before it, and so on.
+    fn at(&self, index: isize) -> Result<&T, IndexError>;
+
+    // Any index works: it wraps round, so with 3 items, 3 is the first and -1 is the last.
+    // Only an empty slice gives an error.
+    fn wrapping_at(&self, index: isize) -> Result<&T, IndexError>;
+
+    // Any index works: below 0 gives the first item and past the end gives the last.
+    // Only an empty slice gives an error.
+    fn clamped_at(&self, index: isize) -> Result<&T, IndexError>;
+
+    // The items from start up to (but not including) end, like &slice[start..end].
+    fn sub_slice(&self, start: usize, end: usize) -> Result<&[T], IndexError>;
+}
+
+impl<T> SafeIndex<T> for [T] {
+    fn first_or<'a>(&'a self, default: &'a T) -> &'a T {
+        self.first().unwrap_or(default)
+    }
+
+    fn nth_checked(&self, n: usize) -> Result<&T, IndexError> {
+        self.get(n).ok_or(IndexError::OutOfRange {
+            index: n as isize,
+            len: self.len(),
+        })
+    }
+
+    fn at(&self, index: isize) -> Result<&T, IndexError> {
+        let len = self.len() as isize;
+        let actual = if index < 0 { len + index } else { index };
+        if actual < 0 || actual >= len {
+            return Err(IndexError::OutOfRange {
+                index,
+                len: self.len(),
+            });
+        }
+        Ok(&self[actual as usize])
+    }
+
+    fn wrapping_at(&self, index: isize) -> Result<&T, IndexError> {
+        if self.is_empty() {
+            return Err(IndexError::Empty);
+        }
+        // rem_euclid is like % but never negative: (-1).rem_euclid(3) is 2, where -1 % 3 is -1.
+        Ok(&self[index.rem_euclid(self.len() as isize) as usize])
+    }
+
+    fn clamped_at(&self, index: isize) -> Result<&T, IndexError> {
+        if self.is_empty() {
+            return Err(IndexError::Empty);
+        }
+        Ok(&self[index.clamp(0, self.len() as isize - 1) as usize])
+    }
+
+    fn sub_slice(&self, start: usize, end: usize) -> Result<&[T], IndexError> {
+        self.get(start..end).ok_or(IndexError::BadRange {
+            start,
+            end,
+            len: self.len(),
+        })
+    }
+}
@@ -0,0 +1,311 @@
+// Sessions: remembering who is logged in after a LoginStatus::Success.
+//
+// Checking a password on every request would be slow (the hash is slow on
+// purpose) and would mean keeping the password around. Instead, a successful
+// login starts a session and hands back a token: a long random string the
+// user sends with each request to prove who they are.
+//
+// A token looks like "<session id>.<signature>". The id is random, and the
+// signature is an HMAC of the id made with a secret key only the server
+// knows. A token that has been changed, or made up, has the wrong signature,
+// so it is rejected before the session is even looked up. The token is
+// "opaque": it doesn't contain the username or anything else readable.
+//
+// A session ends when:
+// - it reaches its lifetime (it expires), even if it's being used,
+// - it isn't used for longer than the idle timeout, or
+// - it is revoked, like when the user logs out.
+// Refreshing swaps a token for a new one with a fresh lifetime.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use rand::Rng;
+
+use crate::utils::auth::now_seconds;
+use crate::utils::hashing;
+
+// Why a token was turned down.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionError {
+    // Not shaped like a token at all.
+    Malformed,
+    // The signature doesn't match, so the token was changed or made up.
+    Tampered,
+    // The session reached the end of its lifetime.
+    Expired,
+    // The session wasn't used for too long.
+    IdleTimeout,
+    // The session was ended on purpose, like by logging out.
+    Revoked,
+    // Correctly signed, but there's no such session (it was cleared away).
+    Unknown,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            SessionError::Malformed => "That isn't a session token",
+            SessionError::Tampered => "The session token has been tampered with",
+            SessionError::Expired => "The session has expired; please log in again",
+            SessionError::IdleTimeout => "The session timed out after being idle; please log in again",
+            SessionError::Revoked => "The session has been ended; please log in again",
+            SessionError::Unknown => "There is no such session; please log in again",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub username: String,
+    // Unix times, in seconds.
+    pub started: u64,
+    pub expires: u64,
+    pub last_used: u64,
+}
+
+pub struct SessionManager {
+    // The secret the signatures are made with.
+    key: Vec<u8>,
+    // Session ids (in hex) to their sessions.
+    sessions: HashMap<String, Session>,
+    // Revoked session ids, and when each would have expired anyway. After
+    // that time there's no need to remember them.
+    revoked: HashMap<String, u64>,
+    // How long a session lasts, in seconds.
+    pub lifetime: u64,
+    // How long a session can go unused, in seconds.
+    pub idle_timeout: u64,
+    // Where the time comes from. Tests can swap in a fake clock, like UserStore's.
+    clock: fn() -> u64,
+}
+
+impl Default for SessionManager {
+    fn default() -> Self {
+        SessionManager::new()
+    }
+}
+
+impl SessionManager {
+    // A manager with a new random key. Tokens from a different manager won't work.
+    pub fn new() -> SessionManager {
+        let mut key = [0u8; 32];
+        rand::rng().fill(&mut key);
+        SessionManager::with_key(&key)
+    }
+
+    pub fn with_key(key: &[u8]) -> SessionManager {
+        SessionManager {
+            key: key.to_vec(),
+            sessions: HashMap::new(),
+            revoked: HashMap::new(),
+            lifetime: 8 * 60 * 60,
+            idle_timeout: 30 * 60,
+            clock: now_seconds,
+        }
+    }
+
+    // Only the tests below use this for now.
+    #[allow(dead_code)]
+    pub fn set_clock(&mut self, clock: fn() -> u64) {
+        self.clock = clock;
+    }
+
+    fn sign(&self, id: &str) -> String {
+        hashing::to_hex(&hashing::hmac_sha256(&self.key, id.as_bytes()))
+    }
+
+    // Starts a session and returns its token.
+    pub fn start(&mut self, username: &str) -> String {
+        let mut id = [0u8; 16];
+        rand::rng().fill(&mut id);
+        let id = hashing::to_hex(&id);
+        let now = (self.clock)();
+        let session = Session {
+            username: username.to_string(),
+            started: now,
+            expires: now.saturating_add(self.lifetime),
+            last_used: now,
+        };
+        let token = format!("{}.{}", id, self.sign(&id));
+        self.sessions.insert(id, session);
+        token
+    }
+
+    // Checks the signature and returns the session id.
+    fn session_id<'a>(&self, token: &'a str) -> Result<&'a str, SessionError> {
+        let (id, signature) = token.trim().split_once('.').ok_or(SessionError::Malformed)?;
+        if id.is_empty() || signature.is_empty() {
+            return Err(SessionError::Malformed);
+        }
+        if !hashing::constant_time_eq(self.sign(id).as_bytes(), signature.as_bytes()) {
+            return Err(SessionError::Tampered);
+        }
+        Ok(id)
+    }
+
+    // Checks a token and marks the session as just used. An expired or idle
+    // session is removed, so the next check gives Unknown.
+    pub fn verify(&mut self, token: &str) -> Result<&Session, SessionError> {
+        let id = self.session_id(token)?;
+        let now = (self.clock)();
+        if self.revoked.contains_key(id) {
+            return Err(SessionError::Revoked);
+        }
+        let session = self.sessions.get(id).ok_or(SessionError::Unknown)?;
+        if now >= session.expires {
+            self.sessions.remove(id);
+            return Err(SessionError::Expired);
+        }
+        if now.saturating_sub(session.last_used) >= self.idle_timeout {
+            self.sessions.remove(id);
+            return Err(SessionError::IdleTimeout);
+        }
+        let session = self.sessions.get_mut(id).ok_or(SessionError::Unknown)?;
+        session.last_used = now;
+        Ok(session)
+    }
+
+    // Swaps a working token for a new one with a fresh lifetime. The old token stops working.
+    pub fn refresh(&mut self, token: &str) -> Result<String, SessionError> {
+        let username = self.verify(token)?.username.clone();
+        self.revoke(token)?;
+        Ok(self.start(&username))
+    }
+
+    // Ends a session, like logging out.
+    pub fn revoke(&mut self, token: &str) -> Result<(), SessionError> {
+        let id = self.session_id(token)?;
+        let session = self.sessions.remove(id).ok_or(SessionError::Unknown)?;
+        self.revoked.insert(id.to_string(), session.expires);
+        Ok(())
+    }
+
+    // Ends every session a user has, like "log out everywhere". Returns how many there were.
+    // Usernames ignore upper and lower case, like in UserStore, so "John" also ends john's sessions.
+    pub fn revoke_user(&mut self, username: &str) -> usize {
+        let ids: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.username.eq_ignore_ascii_case(username))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &ids {
+            if let Some(session) = self.sessions.remove(id) {
+                self.revoked.insert(id.clone(), session.expires);
+            }
+        }
+        ids.len()
+    }
+
+    // Clears away sessions that have ended, and revoked ids that would have
+    // expired by now anyway. Returns how many sessions were removed.
+    pub fn remove_expired(&mut self) -> usize {
+        let now = (self.clock)();
+        let idle_timeout = self.idle_timeout;
+        let before = self.sessions.len();
+        self.sessions
+            .retain(|_, session| now < session.expires && now.saturating_sub(session.last_used) < idle_timeout);
+        self.revoked.retain(|_, expires| now < *expires);
+        before - self.sessions.len()
+    }
+
+    // The sessions that haven't been cleared away yet.
+    pub fn sessions(&self) -> impl Iterator<Item = &Session> {
+        self.sessions.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // Each test runs on its own thread, so each gets its own fake time.
+    thread_local! {
+        static NOW: Cell<u64> = const { Cell::new(1_000_000) };
+    }
+
+    fn fake_clock() -> u64 {
+        NOW.with(|now| now.get())
+    }
+
+    fn wait(seconds: u64) {
+        NOW.with(|now| now.set(now.get() + seconds));
+    }
+
+    fn manager() -> SessionManager {
+        let mut manager = SessionManager::with_key(b"test key");
+        manager.set_clock(fake_clock);
+        manager.lifetime = 100;
+        manager.idle_timeout = 40;
+        manager
+    }
+
+    #[test]
+    fn sessions_expire_even_when_used() {
+        let mut manager = manager();
+        let token = manager.start("ann");
+        for _ in 0..3 {
+            wait(30);
+            assert_eq!(manager.verify(&token).map(|s| s.username.clone()), Ok(String::from("ann")));
+        }
+        wait(10);
+        assert_eq!(manager.verify(&token).err(), Some(SessionError::Expired));
+        assert_eq!(manager.verify(&token).err(), Some(SessionError::Unknown));
+    }
+
+    #[test]
+    fn unused_sessions_time_out() {
+        let mut manager = manager();
+        let token = manager.start("ann");
+        wait(39);
+        assert!(manager.verify(&token).is_ok());
+        wait(40);
+        assert_eq!(manager.verify(&token).err(), Some(SessionError::IdleTimeout));
+    }
+
+    #[test]
+    fn changed_tokens_are_tampered() {
+        let mut manager = manager();
+        let token = manager.start("ann");
+        let (id, signature) = token.split_once('.').unwrap();
+        let flipped = if id.starts_with('0') { '1' } else { '0' };
+        let changed = format!("{}{}.{}", flipped, &id[1..], signature);
+        assert_eq!(manager.verify(&changed).err(), Some(SessionError::Tampered));
+        // A token signed with a different key doesn't work either.
+        let other = SessionManager::with_key(b"another key").start("ann");
+        assert_eq!(manager.verify(&other).err(), Some(SessionError::Tampered));
+        assert_eq!(manager.verify("not a token").err(), Some(SessionError::Malformed));
+    }
+
+    #[test]
+    fn revoked_and_refreshed_tokens_stop_working() {
+        let mut manager = manager();
+        let token = manager.start("ann");
+        manager.revoke(&token).unwrap();
+        assert_eq!(manager.verify(&token).err(), Some(SessionError::Revoked));
+
+        let old = manager.start("ann");
+        wait(35);
+        assert!(manager.verify(&old).is_ok());
+        wait(35);
+        let new = manager.refresh(&old).unwrap();
+        assert_eq!(manager.verify(&old).err(), Some(SessionError::Revoked));
+        // The new token gets a whole new lifetime: the old one would have expired by now.
+        wait(35);
+        assert!(manager.verify(&new).is_ok());
+    }
+
+    #[test]
+    fn revoke_user_ignores_case() {
+        let mut manager = manager();
+        let tokens = [manager.start("john"), manager.start("John"), manager.start("ann")];
+        assert_eq!(manager.revoke_user("JOHN"), 2);
+        assert_eq!(manager.verify(&tokens[0]).err(), Some(SessionError::Revoked));
+        assert_eq!(manager.verify(&tokens[1]).err(), Some(SessionError::Revoked));
+        assert!(manager.verify(&tokens[2]).is_ok());
+        assert_eq!(manager.sessions().count(), 1);
+    }
+}
@@ -0,0 +1,341 @@
+// A shopping list manager, built on the Vec operations from the vectors lesson.
+//
+// main.rs pushes, pops, inserts, removes, resizes and clears a Vec<&str> of
+// vegetables. Here the same operations run on named lists of items with
+// quantities and categories. Every change can be undone, and the lists are
+// saved as JSON so they're still there next time.
+
+use std::fs;
+
+use crate::utils::json::{self, FromJson, JsonValue, ToJson};
+
+// How many changes `undo` can go back.
+const MAX_UNDO: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub name: String,
+    pub quantity: u32,
+    pub category: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShoppingList {
+    pub name: String,
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortBy {
+    Name,
+    Category,
+    Quantity,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListManager {
+    pub lists: Vec<ShoppingList>,
+    // Copies of `lists` from before each change. Undo pops from here and pushes onto redo.
+    undo: Vec<Vec<ShoppingList>>,
+    redo: Vec<Vec<ShoppingList>>,
+}
+
+// Names are compared ignoring case, so "carrot" finds "Carrot".
+fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+fn check_name(kind: &str, name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        Err(format!("The {} name can't be empty", kind))
+    } else {
+        Ok(())
+    }
+}
+
+fn find_list<'a>(lists: &'a mut [ShoppingList], name: &str) -> Result<&'a mut ShoppingList, String> {
+    lists
+        .iter_mut()
+        .find(|l| same_name(&l.name, name))
+        .ok_or_else(|| format!("There is no list called {}", name))
+}
+
+impl ShoppingList {
+    fn item_index(&self, name: &str) -> Result<usize, String> {
+        self.items
+            .iter()
+            .position(|i| same_name(&i.name, name))
+            .ok_or_else(|| format!("{} is not on the {} list", name, self.name))
+    }
+}
+
+impl ListManager {
+    pub fn new() -> ListManager {
+        ListManager::default()
+    }
+
+    // Runs a change on a copy of the lists. If it works, the copy replaces the
+    // lists and the old version is kept for undo. If it fails, nothing changes.
+    fn edit<F>(&mut self, change: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Vec<ShoppingList>) -> Result<(), String>,
+    {
+        let mut updated = self.lists.clone();
+        change(&mut updated)?;
+        let previous = std::mem::replace(&mut self.lists, updated);
+        self.undo.push(previous);
+        if self.undo.len() > MAX_UNDO {
+            // remove(0) shifts everything down by one, like in the vectors lesson.
+            self.undo.remove(0);
+        }
+        // A new change means the old redo steps no longer make sense.
+        self.redo.clear();
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> Result<(), String> {
+        let previous = self.undo.pop().ok_or_else(|| String::from("There is nothing to undo"))?;
+        self.redo.push(std::mem::replace(&mut self.lists, previous));
+        Ok(())
+    }
+
+    pub fn redo(&mut self) -> Result<(), String> {
+        let next = self.redo.pop().ok_or_else(|| String::from("There is nothing to redo"))?;
+        self.undo.push(std::mem::replace(&mut self.lists, next));
+        Ok(())
+    }
+
+    pub fn list(&self, name: &str) -> Option<&ShoppingList> {
+        self.lists.iter().find(|l| same_name(&l.name, name))
+    }
+
+    pub fn create_list(&mut self, name: &str) -> Result<(), String> {
+        check_name("list", name)?;
+        self.edit(|lists| {
+            if lists.iter().any(|l| same_name(&l.name, name)) {
+                return Err(format!("There is already a list called {}", name));
+            }
+            lists.push(ShoppingList {
+                name: name.to_string(),
+                items: Vec::new(),
+            });
+            Ok(())
+        })
+    }
+
+    pub fn delete_list(&mut self, name: &str) -> Result<(), String> {
+        self.edit(|lists| {
+            let before = lists.len();
+            // retain keeps only the items where the closure returns true.
+            lists.retain(|l| !same_name(&l.name, name));
+            if lists.len() == before {
+                return Err(format!("There is no list called {}", name));
+            }
+            Ok(())
+        })
+    }
+
+    pub fn rename_list(&mut self, name: &str, new_name: &str) -> Result<(), String> {
+        check_name("list", new_name)?;
+        self.edit(|lists| {
+            if !same_name(name, new_name) && lists.iter().any(|l| same_name(&l.name, new_name)) {
+                return Err(format!("There is already a list called {}", new_name));
+            }
+            find_list(lists, name)?.name = new_name.to_string();
+            Ok(())
+        })
+    }
+
+    // Adds an item to the end of a list, like push().
+    pub fn add_item(&mut self, list: &str, name: &str, quantity: u32, category: &str) -> Result<(), String> {
+        check_name("item", name)?;
+        if quantity == 0 {
+            return Err(String::from("The quantity must be at least 1"));
+        }
+        self.edit(|lists| {
+            find_list(lists, list)?.items.push(Item {
+                name: name.to_string(),
+                quantity,
+                category: category.to_string(),
+            });
+            Ok(())
+        })
+    }
+
+    // Removes an item, like remove(index), and returns nothing because the
+    // removed item is kept in the undo history anyway.
+    pub fn remove_item(&mut self, list: &str, name: &str) -> Result<(), String> {
+        self.edit(|lists| {
+            let list = find_list(lists, list)?;
+            let index = list.item_index(name)?;
+            list.items.remove(index);
+            Ok(())
+        })
+    }
+
+    pub fn rename_item(&mut self, list: &str, name: &str, new_name: &str) -> Result<(), String> {
+        check_name("item", new_name)?;
+        self.edit(|lists| {
+            let list = find_list(lists, list)?;
+            let index = list.item_index(name)?;
+            list.items[index].name = new_name.to_string();
+            Ok(())
+        })
+    }
+
+    // Moves an item to a new position in the same list (1 is the top).
+    pub fn move_item(&mut self, list: &str, name: &str, position: usize) -> Result<(), String> {
+        self.edit(|lists| {
+            let list = find_list(lists, list)?;
+            if position == 0 || position > list.items.len() {
+                return Err(format!("Position {} is outside 1 to {}", position, list.items.len()));
+            }
+            let index = list.item_index(name)?;
+            // remove() then insert() is how you move something in a Vec.
+            let item = list.items.remove(index);
+            list.items.insert(position - 1, item);
+            Ok(())
+        })
+    }
+
+    // Moves an item onto the end of another list.
+    pub fn move_to_list(&mut self, from: &str, name: &str, to: &str) -> Result<(), String> {
+        self.edit(|lists| {
+            find_list(lists, to)?;
+            let source = find_list(lists, from)?;
+            let index = source.item_index(name)?;
+            let item = source.items.remove(index);
+            find_list(lists, to)?.items.push(item);
+            Ok(())
+        })
+    }
+
+    pub fn sort_list(&mut self, list: &str, by: SortBy) -> Result<(), String> {
+        self.edit(|lists| {
+            let items = &mut find_list(lists, list)?.items;
+            match by {
+                SortBy::Name => items.sort_by_key(|i| i.name.to_lowercase()),
+                // Within a category, items are sorted by name.
+                SortBy::Category => items.sort_by_key(|i| (i.category.to_lowercase(), i.name.to_lowercase())),
+                // Biggest quantity first. Reverse flips the order of whatever is inside it.
+                SortBy::Quantity => items.sort_by_key(|i| std::cmp::Reverse(i.quantity)),
+            }
+            Ok(())
+        })
+    }
+
+    // Merges items with the same name, adding up their quantities. The first one's
+    // position and category are kept. Returns how many duplicates were merged.
+    pub fn dedupe_list(&mut self, list: &str) -> Result<usize, String> {
+        let mut merged = 0;
+        self.edit(|lists| {
+            let list = find_list(lists, list)?;
+            let mut kept: Vec<Item> = Vec::new();
+            for item in list.items.drain(..) {
+                match kept.iter_mut().find(|k| same_name(&k.name, &item.name)) {
+                    Some(existing) => {
+                        // checked_add gives None instead of panicking if the total is too big for a u32.
+                        // Returning Err leaves the list as it was, because edit() works on a copy.
+                        existing.quantity = existing
+                            .quantity
+                            .checked_add(item.quantity)
+                            .ok_or_else(|| format!("The total quantity of {} is too big to merge", existing.name))?;
+                        merged += 1;
+                    }
+                    None => kept.push(item),
+                }
+            }
+            list.items = kept;
+            Ok(())
+        })?;
+        Ok(merged)
+    }
+
+    pub fn clear_list(&mut self, list: &str) -> Result<(), String> {
+        self.edit(|lists| {
+            find_list(lists, list)?.items.clear();
+            Ok(())
+        })
+    }
+
+    // Saves the lists and the undo/redo history, so undo still works next time.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_json().pretty()).map_err(|e| format!("Could not write {}: {}", path, e))
+    }
+
+    // Loads the lists. A missing file gives an empty manager.
+    pub fn load(path: &str) -> Result<ListManager, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ListManager::new()),
+            Err(e) => return Err(format!("Could not read {}: {}", path, e)),
+        };
+        let value = json::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+        ListManager::from_json(&value).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+impl ToJson for Item {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            (String::from("name"), self.name.to_json()),
+            (String::from("quantity"), self.quantity.to_json()),
+            (String::from("category"), self.category.to_json()),
+        ])
+    }
+}
+
+impl FromJson for Item {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        Ok(Item {
+            name: String::from_json(value.field("name")?)?,
+            quantity: u32::from_json(value.field("quantity")?)?,
+            category: String::from_json(value.field("category")?)?,
+        })
+    }
+}
+
+impl ToJson for ShoppingList {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            (String::from("name"), self.name.to_json()),
+            (String::from("items"), self.items.to_json()),
+        ])
+    }
+}
+
+impl FromJson for ShoppingList {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        Ok(ShoppingList {
+            name: String::from_json(value.field("name")?)?,
+            items: Vec::from_json(value.field("items")?).map_err(|e| format!("items: {}", e))?,
+        })
+    }
+}
+
+impl ToJson for ListManager {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            (String::from("lists"), self.lists.to_json()),
+            (String::from("undo"), self.undo.to_json()),
+            (String::from("redo"), self.redo.to_json()),
+        ])
+    }
+}
+
+impl FromJson for ListManager {
+    fn from_json(value: &JsonValue) -> Result<Self, String> {
+        // Older files might not have any history saved, so undo and redo are optional.
+        let history = |key: &str| -> Result<Vec<Vec<ShoppingList>>, String> {
+            match value.get(key) {
+                Some(v) => Vec::from_json(v).map_err(|e| format!("{}: {}", key, e)),
+                None => Ok(Vec::new()),
+            }
+        };
+        Ok(ListManager {
+            lists: Vec::from_json(value.field("lists")?).map_err(|e| format!("lists: {}", e))?,
+            undo: history("undo")?,
+            redo: history("redo")?,
+        })
+    }
+}
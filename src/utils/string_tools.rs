@@ -0,0 +1,193 @@
+// Safe, character-based indexing for Strings.
+//
+// main.rs explains that you can't write my_string[3] in Rust, because Strings
+// are UTF-8 and one character can take 1 to 4 bytes. The workarounds shown
+// there, chars().nth(0).unwrap() and collecting into a Vec<char>, panic if the
+// index is too big. The functions here return an Option or a Result instead.
+//
+// There are two ways to count "characters":
+// - A char is one Unicode code point. 'é' can be one char, or 'e' plus a separate accent mark.
+// - A grapheme is what a person would call one character on screen. "é" is always one
+//   grapheme, and so is a family emoji like 👨‍👩‍👧, even though it is 5 chars joined together.
+// Every function has a char version and a grapheme_ version.
+
+use std::fmt;
+
+// This crate knows the Unicode rules for where one grapheme ends and the next begins.
+// It was added with `cargo add unicode-segmentation`.
+use unicode_segmentation::UnicodeSegmentation;
+
+// Returned when an index is past the end of the string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StringIndexError {
+    pub index: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for StringIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index {} is out of range for a length of {}", self.index, self.len)
+    }
+}
+
+// Whether to count in chars or graphemes.
+#[derive(Clone, Copy)]
+enum Unit {
+    Char,
+    Grapheme,
+}
+
+// The byte offset where each char or grapheme starts, plus the length of the
+// string at the end. For "héllo" in chars that's [0, 1, 3, 4, 5, 6], because é is 2 bytes.
+fn boundaries(s: &str, unit: Unit) -> Vec<usize> {
+    let mut offsets: Vec<usize> = match unit {
+        Unit::Char => s.char_indices().map(|(i, _)| i).collect(),
+        Unit::Grapheme => s.grapheme_indices(true).map(|(i, _)| i).collect(),
+    };
+    offsets.push(s.len());
+    offsets
+}
+
+fn get(s: &str, index: usize, unit: Unit) -> Option<&str> {
+    let b = boundaries(s, unit);
+    if index + 1 < b.len() {
+        Some(&s[b[index]..b[index + 1]])
+    } else {
+        None
+    }
+}
+
+fn slice(s: &str, start: usize, end: usize, unit: Unit) -> Option<&str> {
+    let b = boundaries(s, unit);
+    if start <= end && end < b.len() {
+        Some(&s[b[start]..b[end]])
+    } else {
+        None
+    }
+}
+
+fn replace(s: &mut String, index: usize, with: &str, unit: Unit) -> Result<String, StringIndexError> {
+    let b = boundaries(s, unit);
+    if index + 1 >= b.len() {
+        return Err(StringIndexError { index, len: b.len() - 1 });
+    }
+    let old = s[b[index]..b[index + 1]].to_string();
+    s.replace_range(b[index]..b[index + 1], with);
+    Ok(old)
+}
+
+fn insert(s: &mut String, index: usize, text: &str, unit: Unit) -> Result<(), StringIndexError> {
+    let b = boundaries(s, unit);
+    // Inserting at the length is allowed: it adds to the end, like Vec::insert.
+    match b.get(index) {
+        Some(&offset) => {
+            s.insert_str(offset, text);
+            Ok(())
+        }
+        None => Err(StringIndexError { index, len: b.len() - 1 }),
+    }
+}
+
+fn remove(s: &mut String, index: usize, unit: Unit) -> Result<String, StringIndexError> {
+    let b = boundaries(s, unit);
+    if index + 1 >= b.len() {
+        return Err(StringIndexError { index, len: b.len() - 1 });
+    }
+    // drain() takes the bytes out of the String and gives them back to us.
+    Ok(s.drain(b[index]..b[index + 1]).collect())
+}
+
+// The number of chars, which is often different from s.len() (the number of bytes).
+pub fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+// The char at an index, or None instead of a panic.
+pub fn char_at(s: &str, index: usize) -> Option<char> {
+    s.chars().nth(index)
+}
+
+// Replaces the char at an index and returns the one that was there.
+// This is the safe version of the Vec<char> trick in main.rs.
+pub fn replace_char(s: &mut String, index: usize, c: char) -> Result<char, StringIndexError> {
+    let old = replace(s, index, c.encode_utf8(&mut [0; 4]), Unit::Char)?;
+    Ok(old.chars().next().unwrap_or(c))
+}
+
+// Inserts a char so it ends up at the given index. The index can be the length, to add to the end.
+#[allow(dead_code)]
+pub fn insert_char(s: &mut String, index: usize, c: char) -> Result<(), StringIndexError> {
+    insert(s, index, c.encode_utf8(&mut [0; 4]), Unit::Char)
+}
+
+// Removes the char at an index and returns it.
+#[allow(dead_code)]
+pub fn remove_char(s: &mut String, index: usize) -> Result<char, StringIndexError> {
+    let removed = remove(s, index, Unit::Char)?;
+    // We removed exactly one char, so there is always one to take out.
+    Ok(removed.chars().next().unwrap_or_default())
+}
+
+// The chars from start up to (but not including) end, like s[start..end] counted in chars.
+pub fn char_slice(s: &str, start: usize, end: usize) -> Option<&str> {
+    slice(s, start, end, Unit::Char)
+}
+
+// Converts a char index into the byte offset where that char starts.
+// The char count itself converts to s.len(), the end of the string.
+#[allow(dead_code)]
+pub fn char_to_byte(s: &str, char_index: usize) -> Option<usize> {
+    boundaries(s, Unit::Char).get(char_index).copied()
+}
+
+// Converts a byte offset into a char index. Returns None if the offset is in
+// the middle of a multi-byte char, where slicing would panic.
+#[allow(dead_code)]
+pub fn byte_to_char(s: &str, byte_index: usize) -> Option<usize> {
+    if !s.is_char_boundary(byte_index) {
+        return None;
+    }
+    Some(s[..byte_index].chars().count())
+}
+
+// The number of graphemes (characters as a person would count them).
+pub fn grapheme_len(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+pub fn grapheme_at(s: &str, index: usize) -> Option<&str> {
+    get(s, index, Unit::Grapheme)
+}
+
+// Replaces the grapheme at an index with some text, returning the old grapheme.
+#[allow(dead_code)]
+pub fn replace_grapheme(s: &mut String, index: usize, with: &str) -> Result<String, StringIndexError> {
+    replace(s, index, with, Unit::Grapheme)
+}
+
+#[allow(dead_code)]
+pub fn insert_grapheme(s: &mut String, index: usize, text: &str) -> Result<(), StringIndexError> {
+    insert(s, index, text, Unit::Grapheme)
+}
+
+#[allow(dead_code)]
+pub fn remove_grapheme(s: &mut String, index: usize) -> Result<String, StringIndexError> {
+    remove(s, index, Unit::Grapheme)
+}
+
+#[allow(dead_code)]
+pub fn grapheme_slice(s: &str, start: usize, end: usize) -> Option<&str> {
+    slice(s, start, end, Unit::Grapheme)
+}
+
+#[allow(dead_code)]
+pub fn grapheme_to_byte(s: &str, grapheme_index: usize) -> Option<usize> {
+    boundaries(s, Unit::Grapheme).get(grapheme_index).copied()
+}
+
+// Converts a byte offset into a grapheme index. Returns None unless the
+// offset is exactly where a grapheme starts (or the end of the string).
+#[allow(dead_code)]
+pub fn byte_to_grapheme(s: &str, byte_index: usize) -> Option<usize> {
+    boundaries(s, Unit::Grapheme).iter().position(|&b| b == byte_index)
+}
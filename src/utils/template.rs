@@ -0,0 +1,593 @@
+// A template engine: like format!, but the template is read while the program runs.
+//
+// format!("Hello, {}!", name) is checked when you compile, so the template has to
+// be written in your code. render() takes the template as a normal string, for
+// example one loaded from a file, and fills it in from a HashMap of values:
+//
+//     Hello, {name}!                         a placeholder
+//     {user.name}                            a field inside a Map value
+//     {price:>8.2}                           a format spec, like format!'s {:>8.2}
+//     {#if logged_in}Welcome!{#else}Please log in.{/if}
+//     {#for fruit in fruits}- {fruit}{/for}
+//     {{ and }}                              a literal { and }
+//
+// Format specs support fill and alignment (<, ^, >), a leading 0 for zero
+// padding, a width, and a .precision, in the same order as format!.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::utils::json::JsonValue;
+
+// The values a template can use.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    List(Vec<Value>),
+    Map(HashMap<String, Value>),
+}
+
+// These let you write Value::from("Alice") or 42.into() instead of Value::Text(String::from("Alice")).
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Value::List(items.into_iter().map(|item| item.into()).collect())
+    }
+}
+
+// Turns parsed JSON into template values, so data can come from a .json file.
+// JSON null becomes an empty string.
+impl From<&JsonValue> for Value {
+    fn from(json: &JsonValue) -> Self {
+        match json {
+            JsonValue::Null => Value::Text(String::new()),
+            JsonValue::Bool(b) => Value::Bool(*b),
+            JsonValue::Number(n) => Value::Number(*n),
+            JsonValue::String(s) => Value::Text(s.clone()),
+            JsonValue::Array(items) => Value::List(items.iter().map(Value::from).collect()),
+            JsonValue::Object(pairs) => Value::Map(pairs.iter().map(|(k, v)| (k.clone(), Value::from(v))).collect()),
+        }
+    }
+}
+
+impl Value {
+    // Whether {#if ...} counts this value as true.
+    // Empty text, 0, false, and empty lists and maps are false; everything else is true.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Text(s) => !s.is_empty(),
+            Value::Number(n) => *n != 0.0,
+            Value::Bool(b) => *b,
+            Value::List(items) => !items.is_empty(),
+            Value::Map(map) => !map.is_empty(),
+        }
+    }
+}
+
+// Where something went wrong in the template. Lines and columns start at 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+// A position in the template, kept so errors can point at the right place.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    fn error(&self, message: &str) -> TemplateError {
+        TemplateError {
+            line: self.line,
+            column: self.column,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// The part after the colon in {price:>8.2}.
+#[derive(Debug, Clone, PartialEq)]
+struct FormatSpec {
+    fill: char,
+    align: Option<Align>,
+    zero_pad: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+// The template after parsing, as a tree: an {#if} or {#for} holds the nodes inside it.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Placeholder {
+        path: Vec<String>,
+        spec: FormatSpec,
+        position: Position,
+    },
+    If {
+        path: Vec<String>,
+        negate: bool,
+        then_nodes: Vec<Node>,
+        else_nodes: Vec<Node>,
+    },
+    For {
+        variable: String,
+        path: Vec<String>,
+        body: Vec<Node>,
+        position: Position,
+    },
+}
+
+// The pieces the template is split into before building the tree.
+enum Token {
+    Text(String),
+    // The text between { and }, and where the { was.
+    Tag(String, Position),
+}
+
+fn tokenize(template: &str) -> Result<Vec<Token>, TemplateError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut text = String::new();
+    let mut position = Position { line: 1, column: 1 };
+    let mut chars = template.chars().peekable();
+
+    // Moves the position on past one char.
+    fn advance(position: &mut Position, c: char) {
+        if c == '\n' {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+    }
+
+    while let Some(c) = chars.next() {
+        let start = position;
+        advance(&mut position, c);
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                advance(&mut position, '{');
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                advance(&mut position, '}');
+                text.push('}');
+            }
+            '}' => return Err(start.error("unmatched '}' (write '}}' for a literal brace)")),
+            '{' => {
+                let mut tag = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => {
+                            advance(&mut position, '}');
+                            break;
+                        }
+                        Some('{') => return Err(position.error("'{' inside a placeholder")),
+                        Some(inner) => {
+                            advance(&mut position, inner);
+                            tag.push(inner);
+                        }
+                        None => return Err(start.error("this '{' is never closed")),
+                    }
+                }
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(Token::Tag(tag, start));
+            }
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+// Splits "user.name" into ["user", "name"], checking each part is a sensible name.
+fn parse_path(text: &str, position: Position) -> Result<Vec<String>, TemplateError> {
+    let parts: Vec<String> = text.trim().split('.').map(|p| p.to_string()).collect();
+    for part in &parts {
+        let valid = !part.is_empty()
+            && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+            && !part.chars().next().is_some_and(|c| c.is_numeric());
+        if !valid {
+            return Err(position.error(&format!("'{}' is not a valid name", text.trim())));
+        }
+    }
+    Ok(parts)
+}
+
+// The biggest width and precision a format spec can ask for. format! itself
+// panics on a precision above 65535, and a huge width would try to allocate
+// that many fill characters.
+const MAX_WIDTH: usize = 10_000;
+const MAX_PRECISION: usize = 1_000;
+
+fn parse_spec(text: &str, position: Position) -> Result<FormatSpec, TemplateError> {
+    let mut spec = FormatSpec {
+        fill: ' ',
+        align: None,
+        zero_pad: false,
+        width: 0,
+        precision: None,
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let to_align = |c: char| match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    };
+
+    let mut i = 0;
+    // Either "fill and align" (like *^) or just "align" (like >).
+    if chars.len() >= 2 && to_align(chars[1]).is_some() {
+        spec.fill = chars[0];
+        spec.align = to_align(chars[1]);
+        i = 2;
+    } else if let Some(align) = chars.first().and_then(|c| to_align(*c)) {
+        spec.align = Some(align);
+        i = 1;
+    }
+    if chars.get(i) == Some(&'0') {
+        spec.zero_pad = true;
+        i += 1;
+    }
+
+    // Reads the digits at i, if there are any. `what` and `max` are for the error.
+    let read_number = |i: &mut usize, what: &str, max: usize| -> Result<Option<usize>, TemplateError> {
+        let start = *i;
+        while chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+            *i += 1;
+        }
+        if start == *i {
+            return Ok(None);
+        }
+        let digits: String = chars[start..*i].iter().collect();
+        match digits.parse::<usize>() {
+            Ok(n) if n <= max => Ok(Some(n)),
+            _ => Err(position.error(&format!("the {} {} is too big; the most is {}", what, digits, max))),
+        }
+    };
+    if let Some(width) = read_number(&mut i, "width", MAX_WIDTH)? {
+        spec.width = width;
+    }
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        spec.precision = Some(
+            read_number(&mut i, "precision", MAX_PRECISION)?
+                .ok_or_else(|| position.error("expected a number after '.' in the format spec"))?,
+        );
+    }
+    if i < chars.len() {
+        return Err(position.error(&format!("invalid format spec ':{}'", text)));
+    }
+    Ok(spec)
+}
+
+// What ended a list of nodes: the end of the template, {#else}, {/if} or {/for}.
+#[derive(Debug, PartialEq)]
+enum BlockEnd {
+    Eof,
+    Else,
+    If,
+    For,
+}
+
+// Builds the tree. Called again for the inside of each {#if} and {#for}.
+fn parse_nodes(tokens: &mut std::vec::IntoIter<Token>) -> Result<(Vec<Node>, BlockEnd, Position), TemplateError> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut last_position = Position { line: 1, column: 1 };
+
+    while let Some(token) = tokens.next() {
+        let (tag, position) = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag, position) => (tag, position),
+        };
+        last_position = position;
+        let tag = tag.trim();
+
+        if let Some(condition) = tag.strip_prefix("#if ") {
+            let condition = condition.trim();
+            let (negate, name) = match condition.strip_prefix('!') {
+                Some(name) => (true, name),
+                None => (false, condition),
+            };
+            let path = parse_path(name, position)?;
+            let (then_nodes, end, _) = parse_nodes(tokens)?;
+            let else_nodes = match end {
+                BlockEnd::If => Vec::new(),
+                BlockEnd::Else => {
+                    let (else_nodes, end, _) = parse_nodes(tokens)?;
+                    if end != BlockEnd::If {
+                        return Err(position.error("this {#if} needs a {/if} after its {#else}"));
+                    }
+                    else_nodes
+                }
+                _ => return Err(position.error("this {#if} is never closed with {/if}")),
+            };
+            nodes.push(Node::If {
+                path,
+                negate,
+                then_nodes,
+                else_nodes,
+            });
+        } else if let Some(rest) = tag.strip_prefix("#for ") {
+            let (variable, list) = rest
+                .split_once(" in ")
+                .ok_or_else(|| position.error("expected {#for item in list}"))?;
+            let variable = parse_path(variable, position)?;
+            if variable.len() != 1 {
+                return Err(position.error("the loop variable can't contain a '.'"));
+            }
+            let path = parse_path(list, position)?;
+            let (body, end, _) = parse_nodes(tokens)?;
+            if end != BlockEnd::For {
+                return Err(position.error("this {#for} is never closed with {/for}"));
+            }
+            nodes.push(Node::For {
+                variable: variable[0].clone(),
+                path,
+                body,
+                position,
+            });
+        } else if tag == "#else" {
+            return Ok((nodes, BlockEnd::Else, position));
+        } else if tag == "/if" {
+            return Ok((nodes, BlockEnd::If, position));
+        } else if tag == "/for" {
+            return Ok((nodes, BlockEnd::For, position));
+        } else if tag.starts_with('#') || tag.starts_with('/') {
+            return Err(position.error(&format!("unknown tag {{{}}}", tag)));
+        } else {
+            let (name, spec) = match tag.split_once(':') {
+                Some((name, spec)) => (name, parse_spec(spec, position)?),
+                None => (tag, parse_spec("", position)?),
+            };
+            nodes.push(Node::Placeholder {
+                path: parse_path(name, position)?,
+                spec,
+                position,
+            });
+        }
+    }
+    Ok((nodes, BlockEnd::Eof, last_position))
+}
+
+// A parsed template, ready to be rendered any number of times.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Template, TemplateError> {
+        let mut tokens = tokenize(template)?.into_iter();
+        let (nodes, end, position) = parse_nodes(&mut tokens)?;
+        match end {
+            BlockEnd::Eof => Ok(Template { nodes }),
+            BlockEnd::Else => Err(position.error("{#else} without an {#if}")),
+            BlockEnd::If => Err(position.error("{/if} without an {#if}")),
+            BlockEnd::For => Err(position.error("{/for} without a {#for}")),
+        }
+    }
+
+    pub fn render(&self, values: &HashMap<String, Value>) -> Result<String, TemplateError> {
+        let mut out = String::new();
+        // Loop variables live in their own scopes, searched before the main values.
+        let mut scopes: Vec<(String, Value)> = Vec::new();
+        render_nodes(&self.nodes, values, &mut scopes, &mut out)?;
+        Ok(out)
+    }
+}
+
+// Parses and renders in one go.
+pub fn render(template: &str, values: &HashMap<String, Value>) -> Result<String, TemplateError> {
+    Template::parse(template)?.render(values)
+}
+
+// Finds the value for a path like ["user", "name"].
+// Loop variables are checked first, newest first, so an inner loop hides an outer one.
+fn lookup<'a>(path: &[String], values: &'a HashMap<String, Value>, scopes: &'a [(String, Value)]) -> Option<&'a Value> {
+    let mut value = scopes
+        .iter()
+        .rev()
+        .find(|(name, _)| *name == path[0])
+        .map(|(_, value)| value)
+        .or_else(|| values.get(&path[0]))?;
+    for key in &path[1..] {
+        value = match value {
+            Value::Map(map) => map.get(key)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    values: &HashMap<String, Value>,
+    scopes: &mut Vec<(String, Value)>,
+    out: &mut String,
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Placeholder { path, spec, position } => {
+                let value = lookup(path, values, scopes)
+                    .ok_or_else(|| position.error(&format!("no value for '{}'", path.join("."))))?;
+                out.push_str(&format_value(value, spec, *position)?);
+            }
+            Node::If {
+                path,
+                negate,
+                then_nodes,
+                else_nodes,
+            } => {
+                // A missing value counts as false, so {#if} can check whether something was given.
+                let truthy = lookup(path, values, scopes).is_some_and(|v| v.is_truthy());
+                let branch = if truthy != *negate { then_nodes } else { else_nodes };
+                render_nodes(branch, values, scopes, out)?;
+            }
+            Node::For {
+                variable,
+                path,
+                body,
+                position,
+            } => {
+                let items = match lookup(path, values, scopes) {
+                    Some(Value::List(items)) => items.clone(),
+                    Some(_) => return Err(position.error(&format!("'{}' is not a list", path.join(".")))),
+                    None => return Err(position.error(&format!("no value for '{}'", path.join(".")))),
+                };
+                for item in items {
+                    scopes.push((variable.clone(), item));
+                    let result = render_nodes(body, values, scopes, out);
+                    scopes.pop();
+                    result?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Applies a format spec to a value, following the same rules as format!.
+fn format_value(value: &Value, spec: &FormatSpec, position: Position) -> Result<String, TemplateError> {
+    let (text, is_number) = match value {
+        Value::Number(n) => {
+            let text = match spec.precision {
+                Some(precision) => format!("{:.*}", precision, n),
+                // Whole numbers are shown without ".0", like 42 rather than 42.0.
+                None if n.fract() == 0.0 && n.abs() < 1e15 => format!("{}", *n as i64),
+                None => format!("{}", n),
+            };
+            (text, true)
+        }
+        Value::Text(s) => {
+            // For text, the precision is the maximum number of chars, like format!("{:.3}", "hello") => "hel".
+            let text = match spec.precision {
+                Some(precision) => s.chars().take(precision).collect(),
+                None => s.clone(),
+            };
+            (text, false)
+        }
+        Value::Bool(b) => (b.to_string(), false),
+        Value::List(items) => {
+            let parts: Result<Vec<String>, TemplateError> = items
+                .iter()
+                .map(|item| format_value(item, &FormatSpec { width: 0, ..spec.clone() }, position))
+                .collect();
+            (parts?.join(", "), false)
+        }
+        Value::Map(_) => return Err(position.error("a map can't be shown directly; use one of its fields")),
+    };
+
+    let length = text.chars().count();
+    if length >= spec.width {
+        return Ok(text);
+    }
+    let padding = spec.width - length;
+
+    // Zero padding goes after the minus sign: -0042, not 00-42.
+    if spec.zero_pad && is_number && spec.align.is_none() {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.as_str()),
+        };
+        return Ok(format!("{}{}{}", sign, "0".repeat(padding), digits));
+    }
+
+    // Like format!, numbers line up on the right and text on the left by default.
+    let align = spec.align.unwrap_or(if is_number { Align::Right } else { Align::Left });
+    let fill = |n: usize| spec.fill.to_string().repeat(n);
+    Ok(match align {
+        Align::Left => format!("{}{}", text, fill(padding)),
+        Align::Right => format!("{}{}", fill(padding), text),
+        Align::Center => format!("{}{}{}", fill(padding / 2), text, fill(padding - padding / 2)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_x(template: &str) -> Result<String, TemplateError> {
+        let values = HashMap::from([(String::from("x"), Value::Number(2.5))]);
+        render(template, &values)
+    }
+
+    #[test]
+    fn widths_and_precisions_up_to_the_limit() {
+        assert_eq!(render_x("[{x:>6.2}]").unwrap(), "[  2.50]");
+        assert_eq!(render_x("{x:10000}").unwrap().len(), MAX_WIDTH);
+        assert_eq!(render_x("{x:.1000}").unwrap().len(), MAX_PRECISION + 2);
+    }
+
+    #[test]
+    fn bigger_widths_and_precisions_are_an_error() {
+        let cases = [
+            ("{x:.70000}", "the precision 70000 is too big; the most is 1000"),
+            ("{x:>5000000000}", "the width 5000000000 is too big; the most is 10000"),
+            ("{x:99999999999999999999}", "the width 99999999999999999999 is too big; the most is 10000"),
+            ("{x:.99999999999999999999}", "the precision 99999999999999999999 is too big; the most is 1000"),
+        ];
+        for (template, message) in cases {
+            assert_eq!(render_x(template).unwrap_err().message, message);
+        }
+    }
+}
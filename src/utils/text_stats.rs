@@ -0,0 +1,153 @@
+// Counting things in a piece of text.
+//
+// The strings lesson uses .len() as "the length of the greeting", but len()
+// counts bytes, not characters. For plain English text they're the same, but
+// "héllo" is 6 bytes and 5 chars. TextStats counts bytes, chars, graphemes,
+// words and lines side by side so you can see where they differ and why.
+
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::utils::string_tools;
+
+pub struct TextStats {
+    pub bytes: usize,
+    pub chars: usize,
+    pub graphemes: usize,
+    pub words: usize,
+    pub lines: usize,
+    // Lowercased word => how many times it appears.
+    pub word_counts: HashMap<String, usize>,
+    // Average word length in chars.
+    pub average_word_length: f64,
+    // The line number (starting at 1) and text of the longest line, measured in chars.
+    pub longest_line: Option<(usize, String)>,
+    // Every non-ASCII char => how many times it appears.
+    pub non_ascii: HashMap<char, usize>,
+}
+
+pub fn analyze(text: &str) -> TextStats {
+    let mut word_counts: HashMap<String, usize> = HashMap::new();
+    let mut words = 0;
+    let mut word_chars = 0;
+    // unicode_words() splits on spaces and punctuation, but keeps words like "don't" together.
+    for word in text.unicode_words() {
+        words += 1;
+        word_chars += word.chars().count();
+        *word_counts.entry(word.to_lowercase()).or_insert(0) += 1;
+    }
+
+    let mut longest_line: Option<(usize, String)> = None;
+    let mut lines = 0;
+    for (i, line) in text.lines().enumerate() {
+        lines += 1;
+        let longer = match &longest_line {
+            Some((_, longest)) => line.chars().count() > longest.chars().count(),
+            None => true,
+        };
+        if longer {
+            longest_line = Some((i + 1, line.to_string()));
+        }
+    }
+
+    let mut non_ascii: HashMap<char, usize> = HashMap::new();
+    for c in text.chars().filter(|c| !c.is_ascii()) {
+        *non_ascii.entry(c).or_insert(0) += 1;
+    }
+
+    TextStats {
+        bytes: text.len(),
+        chars: text.chars().count(),
+        graphemes: string_tools::grapheme_len(text),
+        words,
+        lines,
+        word_counts,
+        average_word_length: if words == 0 { 0.0 } else { word_chars as f64 / words as f64 },
+        longest_line,
+        non_ascii,
+    }
+}
+
+impl TextStats {
+    // The n most common words, most common first. Words with the same count are
+    // sorted alphabetically so the output doesn't change from run to run.
+    pub fn top_words(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = self
+            .word_counts
+            .iter()
+            .map(|(word, count)| (word.as_str(), *count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        counts.truncate(n);
+        counts
+    }
+
+    // The non-ASCII chars, most common first, with how many bytes each one takes in UTF-8.
+    pub fn non_ascii_breakdown(&self) -> Vec<(char, usize, usize)> {
+        let mut chars: Vec<(char, usize, usize)> = self
+            .non_ascii
+            .iter()
+            .map(|(c, count)| (*c, *count, c.len_utf8()))
+            .collect();
+        chars.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        chars
+    }
+
+    // A report explaining each number.
+    pub fn report(&self, top: usize) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("Bytes (what .len() returns): {}\n", self.bytes));
+        out.push_str(&format!("Chars (.chars().count()):    {}\n", self.chars));
+        out.push_str(&format!("Graphemes (what you see):    {}\n", self.graphemes));
+        out.push_str(&format!("Words:                       {}\n", self.words));
+        out.push_str(&format!("Lines:                       {}\n", self.lines));
+        out.push_str(&format!("Average word length:         {:.2} chars\n", self.average_word_length));
+
+        if self.bytes != self.chars {
+            // Every ASCII char is 1 byte, so the extra bytes all come from non-ASCII chars.
+            out.push_str(&format!(
+                "\n.len() is {} more than the char count because {} non-ASCII chars take 2 to 4 bytes each.\n",
+                self.bytes - self.chars,
+                self.non_ascii.values().sum::<usize>()
+            ));
+        }
+        if self.chars != self.graphemes {
+            out.push_str(&format!(
+                "There are {} more chars than graphemes, because some things you see as one character \
+                 (like accented letters or emoji) are several chars joined together.\n",
+                self.chars - self.graphemes
+            ));
+        }
+
+        if let Some((number, line)) = &self.longest_line {
+            out.push_str(&format!(
+                "\nLongest line: line {} ({} chars)\n  {}\n",
+                number,
+                line.chars().count(),
+                line
+            ));
+        }
+
+        let top_words = self.top_words(top);
+        if !top_words.is_empty() {
+            out.push_str(&format!("\nTop {} words:\n", top_words.len()));
+            for (word, count) in top_words {
+                out.push_str(&format!("  {:>5}  {}\n", count, word));
+            }
+        }
+
+        let breakdown = self.non_ascii_breakdown();
+        if !breakdown.is_empty() {
+            out.push_str("\nNon-ASCII chars:\n");
+            for (c, count, bytes) in breakdown {
+                // {:?} on a char shows invisible ones (like zero-width joiners) as escapes.
+                out.push_str(&format!(
+                    "  {:>5}  {:?}  U+{:04X}  {} bytes each\n",
+                    count, c, c as u32, bytes
+                ));
+            }
+        }
+        out
+    }
+}
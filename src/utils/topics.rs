@@ -0,0 +1,31 @@
+// A short summary of each topic covered in main.rs, for the `topic` command.
+
+// (name, summary) pairs, in the same order as the lessons in main.rs.
+pub const TOPICS: [(&str, &str); 15] = [
+    ("functions", "Functions are declared with fn. The last line without a semicolon is the return value."),
+    ("variables", "Variables are made with let and are immutable unless you add mut. Rust works out the type from the value."),
+    ("constants", "const values never change, must have a type, and are written in UPPERCASE."),
+    ("strings", "&str is a fixed string slice; String can grow. Strings are UTF-8, so you can't index them with [ ]."),
+    ("arrays", "Arrays like [&str; 3] have a fixed size and one type. Print them with {:?}."),
+    ("vectors", "Vec<T> is a list that can grow and shrink with push, pop, insert, remove, resize and clear."),
+    ("tuples", "Tuples hold a fixed number of values of different types, and can be destructured into variables."),
+    ("hashmaps", "HashMap stores key-value pairs. get() returns an Option because the key might not be there."),
+    ("structs", "A struct groups named fields together, like Person { name, age }."),
+    ("enums", "An enum is one of several variants, like Direction::Up. Variants can hold data, like LoginStatus::Success(String)."),
+    ("operators", "Arithmetic (+ - * / %), comparison (== != < >) and logic (&& || !) work like in Python."),
+    ("control-flow", "if / else if / else choose what to run, and if can return a value. if let matches a single pattern."),
+    ("match", "match compares a value against patterns, like a switch statement, and must cover every case."),
+    ("loops", "loop runs until break (and can return a value), while runs while a condition is true, for walks through a range or collection."),
+    ("ownership", "Each value has one owner. Assigning a String moves it; use clone() to copy it or & to borrow it. Result and Option handle errors."),
+];
+
+pub fn find_topic(name: &str) -> Option<&'static str> {
+    TOPICS
+        .iter()
+        .find(|(topic, _)| topic.eq_ignore_ascii_case(name))
+        .map(|(_, summary)| *summary)
+}
+
+pub fn topic_names() -> Vec<&'static str> {
+    TOPICS.iter().map(|(name, _)| *name).collect()
+}
@@ -0,0 +1,297 @@
+// Time-based one-time passwords (TOTP, RFC 6238): the 6-digit codes from
+// authenticator apps.
+//
+// The server and the app share a secret. Both take the current time, divide
+// it into 30-second steps, and sign the step number with HMAC using the
+// secret (this part is HOTP, RFC 4226). A few digits of the signature are the
+// code. Without the secret you can't work out the code, and each code only
+// works for one step, so a code that's seen over someone's shoulder is
+// useless a minute later.
+//
+// The secret is shared as base32 text (letters A-Z and digits 2-7), usually
+// inside an otpauth:// link that the app reads from a QR code.
+//
+// Phone clocks are rarely exactly right, so a code from a step or two either
+// side of now can be accepted too. That's the drift window.
+
+use std::fmt;
+
+use rand::Rng;
+
+use crate::utils::hashing;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+// The most steps either side of now that verify_at() will try. Each extra
+// step is another code an attacker could guess, so this stays small.
+const MAX_WINDOW: u64 = 10;
+
+// Writes bytes as base32. Every 5 bits become one character. The = padding
+// at the end is left off, as otpauth links do.
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    // Bits waiting to be written, and how many there are.
+    let (mut bits, mut count) = (0u32, 0);
+    for &byte in bytes {
+        bits = (bits << 8) | byte as u32;
+        count += 8;
+        while count >= 5 {
+            count -= 5;
+            text.push(BASE32_ALPHABET[((bits >> count) & 31) as usize] as char);
+        }
+    }
+    if count > 0 {
+        // The last few bits are padded with zeros on the right.
+        text.push(BASE32_ALPHABET[((bits << (5 - count)) & 31) as usize] as char);
+    }
+    text
+}
+
+// Reads base32. Lowercase, spaces, dashes and = padding are all allowed,
+// because people type secrets in by hand.
+pub fn base32_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '-' && *c != '=') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&letter| letter as char == c.to_ascii_uppercase())
+            .ok_or_else(|| format!("'{}' isn't a base32 character; use A-Z and 2-7", c))?;
+        bits = (bits << 5) | value as u32;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Algorithm::Sha1 => write!(f, "SHA1"),
+            Algorithm::Sha256 => write!(f, "SHA256"),
+        }
+    }
+}
+
+impl std::str::FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().replace('-', "").as_str() {
+            "SHA1" => Ok(Algorithm::Sha1),
+            "SHA256" => Ok(Algorithm::Sha256),
+            _ => Err(format!("'{}' isn't a supported algorithm; use sha1 or sha256", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Totp {
+    secret: Vec<u8>,
+    // How long each code lasts, in seconds.
+    pub period: u64,
+    digits: u32,
+    pub algorithm: Algorithm,
+    // How many steps before and after now are also accepted.
+    window: u64,
+}
+
+impl Totp {
+    // The settings every authenticator app understands: 6 digits, 30 seconds, SHA-1.
+    pub fn new(secret: &[u8]) -> Totp {
+        Totp {
+            secret: secret.to_vec(),
+            period: 30,
+            digits: 6,
+            algorithm: Algorithm::Sha1,
+            window: 1,
+        }
+    }
+
+    // A new random secret. 20 bytes is the size RFC 4226 recommends for SHA-1.
+    pub fn random() -> Totp {
+        let mut secret = [0u8; 20];
+        rand::rng().fill(&mut secret);
+        Totp::new(&secret)
+    }
+
+    pub fn from_base32(secret: &str) -> Result<Totp, String> {
+        let secret = base32_decode(secret)?;
+        if secret.is_empty() {
+            return Err(String::from("The secret is empty"));
+        }
+        Ok(Totp::new(&secret))
+    }
+
+    pub fn secret_base32(&self) -> String {
+        base32_encode(&self.secret)
+    }
+
+    #[allow(dead_code)]
+    pub fn digits(&self) -> u32 {
+        self.digits
+    }
+
+    // Codes can be 6, 7 or 8 digits long.
+    pub fn set_digits(&mut self, digits: u32) -> Result<(), String> {
+        if !(6..=8).contains(&digits) {
+            return Err(format!("A code must have 6 to 8 digits, not {}", digits));
+        }
+        self.digits = digits;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn window(&self) -> u64 {
+        self.window
+    }
+
+    // How many steps either side of now to accept: 0 means only the current code.
+    #[allow(dead_code)]
+    pub fn set_window(&mut self, window: u64) -> Result<(), String> {
+        if window > MAX_WINDOW {
+            return Err(format!("The window can be at most {} steps, not {}", MAX_WINDOW, window));
+        }
+        self.window = window;
+        Ok(())
+    }
+
+    // The HOTP code for a counter (RFC 4226).
+    pub fn hotp(&self, counter: u64) -> String {
+        let message = counter.to_be_bytes();
+        let signature: Vec<u8> = match self.algorithm {
+            Algorithm::Sha1 => hashing::hmac_sha1(&self.secret, &message).to_vec(),
+            Algorithm::Sha256 => hashing::hmac_sha256(&self.secret, &message).to_vec(),
+        };
+        // "Dynamic truncation": the last 4 bits pick where to take 4 bytes
+        // from. The top bit is dropped so the number is never negative.
+        let offset = (signature[signature.len() - 1] & 0x0f) as usize;
+        let number = u32::from_be_bytes([
+            signature[offset] & 0x7f,
+            signature[offset + 1],
+            signature[offset + 2],
+            signature[offset + 3],
+        ]);
+        let code = number % 10u32.pow(self.digits);
+        // Leading zeros are part of the code: 1234 is "001234".
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+
+    // Which step a Unix time is in.
+    pub fn step(&self, time: u64) -> u64 {
+        time / self.period.max(1)
+    }
+
+    pub fn code_at(&self, time: u64) -> String {
+        self.hotp(self.step(time))
+    }
+
+    // Checks a code against the steps in the window around `time`. Returns
+    // the step it matched, so the caller can refuse the same code twice.
+    pub fn verify_at(&self, code: &str, time: u64) -> Option<u64> {
+        let code = code.trim();
+        if code.len() != self.digits as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let now = self.step(time);
+        (now.saturating_sub(self.window)..=now.saturating_add(self.window))
+            .find(|&step| hashing::constant_time_eq(self.hotp(step).as_bytes(), code.as_bytes()))
+    }
+
+    // An otpauth:// link for authenticator apps, like:
+    // otpauth://totp/Rust%20Lessons:john?secret=...&issuer=Rust%20Lessons&algorithm=SHA1&digits=6&period=30
+    pub fn uri(&self, issuer: &str, account: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm={}&digits={}&period={}",
+            percent_encode(issuer),
+            percent_encode(account),
+            self.secret_base32(),
+            percent_encode(issuer),
+            self.algorithm,
+            self.digits,
+            self.period
+        )
+    }
+}
+
+// Writes anything other than letters, digits and -._~ as %XX, so it can go in a link.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hotp_matches_rfc_4226() {
+        let totp = Totp::new(b"12345678901234567890");
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(totp.hotp(counter as u64), *code);
+        }
+    }
+
+    // The test vectors from RFC 6238, Appendix B, with 8-digit codes.
+    const TIMES: [u64; 6] = [59, 1111111109, 1111111111, 1234567890, 2000000000, 20000000000];
+
+    #[test]
+    fn totp_sha1_matches_rfc_6238() {
+        let mut totp = Totp::new(b"12345678901234567890");
+        totp.set_digits(8).unwrap();
+        let expected = ["94287082", "07081804", "14050471", "89005924", "69279037", "65353130"];
+        for (time, code) in TIMES.iter().zip(expected) {
+            assert_eq!(totp.code_at(*time), code, "time {}", time);
+        }
+    }
+
+    #[test]
+    fn totp_sha256_matches_rfc_6238() {
+        let mut totp = Totp::new(b"12345678901234567890123456789012");
+        totp.algorithm = Algorithm::Sha256;
+        totp.set_digits(8).unwrap();
+        let expected = ["46119246", "68084774", "67062674", "91819424", "90698825", "77737706"];
+        for (time, code) in TIMES.iter().zip(expected) {
+            assert_eq!(totp.code_at(*time), code, "time {}", time);
+        }
+    }
+
+    #[test]
+    fn verify_accepts_the_window_only() {
+        let mut totp = Totp::new(b"12345678901234567890");
+        let code = totp.code_at(1000);
+        assert_eq!(totp.verify_at(&code, 1000), Some(33));
+        assert_eq!(totp.verify_at(&code, 1030), Some(33));
+        assert_eq!(totp.verify_at(&code, 1060), None);
+        totp.set_window(0).unwrap();
+        assert_eq!(totp.verify_at(&code, 1030), None);
+        assert!(totp.set_window(MAX_WINDOW + 1).is_err());
+        assert_eq!(totp.verify_at("12345", 1000), None);
+    }
+
+    #[test]
+    fn base32_round_trip() {
+        assert_eq!(base32_encode(b"Hello!\xde\xad\xbe\xef"), "JBSWY3DPEHPK3PXP");
+        assert_eq!(base32_decode("jbsw y3dp-ehpk 3pxp").unwrap(), b"Hello!\xde\xad\xbe\xef");
+        assert!(base32_decode("ABC1").is_err());
+    }
+}
@@ -0,0 +1,675 @@
+// Turtle graphics: a tiny drawing language, turned into an SVG picture.
+//
+// Imagine a turtle holding a pen. It starts in the middle facing up, like
+// Direction::Up, and follows commands:
+//
+//     forward 50   (or fd)   move forward, drawing a line if the pen is down
+//     back 50      (or bk)   move backwards
+//     right 90     (or rt)   turn clockwise by some degrees
+//     left 90      (or lt)   turn anticlockwise
+//     face right             turn to face a Direction: up, down, left, right, upleft...
+//     penup        (or pu)   stop drawing
+//     pendown      (or pd)   start drawing again
+//     color red              change the pen colour (a name like red, or #ff8800)
+//     width 3                change the pen width
+//     home                   go back to the middle, facing up
+//     repeat 4 [ fd 50 rt 90 ]            run the commands in brackets 4 times
+//
+// Procedures are made with `to` and `end`. Their inputs start with a colon,
+// and numbers can be worked out with + - * / and brackets:
+//
+//     to square :size
+//       repeat 4 [ fd :size rt 90 ]
+//     end
+//     square 50
+//     square :size * 2     (only inside a procedure that has a :size)
+//
+// A procedure has to be made before the lines that use it.
+// Everything after a ; on a line is a comment (# is used for colours).
+//
+// The program is first parsed into a list of Statements (checking for
+// mistakes), and then run by a Turtle, which records every line it draws.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+
+use crate::Direction;
+
+// Limits so a program like `to spin spin end` or `repeat 100000000 [ ]` stops
+// with an error instead of running forever.
+const MAX_CALL_DEPTH: usize = 100;
+const MAX_STEPS: usize = 100_000;
+// How many repeats (and procedures) can be inside each other.
+const MAX_NESTING: usize = 20;
+// How far from the middle the turtle can go.
+const MAX_DISTANCE: f64 = 1_000_000_000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurtleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TurtleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, TurtleError> {
+    Err(TurtleError { line, message })
+}
+
+// A number, worked out when the program runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    // A procedure input, like :size.
+    Input(String),
+    Negate(Box<Expr>),
+    // Two numbers and one of + - * /.
+    Binary(Box<Expr>, char, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Forward(Expr),
+    Back(Expr),
+    Left(Expr),
+    Right(Expr),
+    Face(Direction),
+    PenUp,
+    PenDown,
+    Color(String),
+    Width(Expr),
+    Home,
+    Repeat(Expr, Vec<(usize, Statement)>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Procedure {
+    pub inputs: Vec<String>,
+    pub body: Vec<(usize, Statement)>,
+}
+
+// A parsed program. Each statement keeps its line number for error messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub procedures: HashMap<String, Procedure>,
+    pub statements: Vec<(usize, Statement)>,
+}
+
+// A word or symbol from the program text, and the line it was on.
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        // Cut off comments.
+        let code = line.split(';').next().unwrap_or("");
+        let mut word = String::new();
+        for c in code.chars() {
+            if c.is_whitespace() || "[]()+-*/".contains(c) {
+                if !word.is_empty() {
+                    tokens.push(Token { text: std::mem::take(&mut word), line: i + 1 });
+                }
+                if !c.is_whitespace() {
+                    tokens.push(Token { text: c.to_string(), line: i + 1 });
+                }
+            } else {
+                word.push(c);
+            }
+        }
+        if !word.is_empty() {
+            tokens.push(Token { text: word, line: i + 1 });
+        }
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    // How many inputs each procedure takes. A procedure is added as soon as its
+    // `to` line is read, so it can call itself.
+    arity: HashMap<String, usize>,
+    procedures: HashMap<String, Procedure>,
+    // How many procedures and repeats we're inside. Procedures can only be made at the top.
+    nesting: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|t| t.text.as_str())
+    }
+
+    // The line of the next token, or of the last one at the end of the program.
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map(|t| t.line)
+            .unwrap_or(1)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    // Reads statements until `end` (inside a procedure), `]` (inside a repeat),
+    // or the end of the program. The closing word itself is left for the caller.
+    fn statements(&mut self, inputs: &[String]) -> Result<Vec<(usize, Statement)>, TurtleError> {
+        let mut statements = Vec::new();
+        while let Some(word) = self.peek() {
+            if word == "]" || word.eq_ignore_ascii_case("end") {
+                break;
+            }
+            let line = self.line();
+            if word.eq_ignore_ascii_case("to") {
+                self.procedure()?;
+                continue;
+            }
+            statements.push((line, self.statement(inputs)?));
+        }
+        Ok(statements)
+    }
+
+    fn procedure(&mut self) -> Result<(), TurtleError> {
+        let line = self.line();
+        if self.nesting > 0 {
+            return error(line, String::from("a procedure can't be made inside a procedure or a repeat"));
+        }
+        self.next(); // `to`
+        let name = match self.next() {
+            Some(token) if is_name(&token.text) => token.text.to_lowercase(),
+            _ => return error(line, String::from("`to` needs a procedure name after it")),
+        };
+        if command_word(&name) {
+            return error(line, format!("'{}' is already a command, so it can't be a procedure name", name));
+        }
+        if self.procedures.contains_key(&name) {
+            return error(line, format!("the procedure '{}' is made twice", name));
+        }
+
+        // The inputs are the :words on the same line as `to`.
+        let mut inputs = Vec::new();
+        while let Some(token) = self.tokens.get(self.position)
+            && token.line == line
+            && token.text.starts_with(':')
+        {
+            let input = token.text[1..].to_lowercase();
+            if !is_name(&input) || inputs.contains(&input) {
+                return error(line, format!("'{}' isn't a good input name", token.text));
+            }
+            inputs.push(input);
+            self.position += 1;
+        }
+        self.arity.insert(name.clone(), inputs.len());
+
+        self.nesting += 1;
+        let body = self.statements(&inputs)?;
+        self.nesting -= 1;
+        match self.next() {
+            Some(token) if token.text.eq_ignore_ascii_case("end") => {}
+            _ => return error(line, format!("the procedure '{}' has no `end`", name)),
+        }
+        self.procedures.insert(name, Procedure { inputs, body });
+        Ok(())
+    }
+
+    fn statement(&mut self, inputs: &[String]) -> Result<Statement, TurtleError> {
+        let token = self.next().expect("statements() checked there is a token");
+        let line = token.line;
+        let word = token.text.to_lowercase();
+        let statement = match word.as_str() {
+            "forward" | "fd" => Statement::Forward(self.expr(inputs)?),
+            "back" | "bk" => Statement::Back(self.expr(inputs)?),
+            "left" | "lt" => Statement::Left(self.expr(inputs)?),
+            "right" | "rt" => Statement::Right(self.expr(inputs)?),
+            "penup" | "pu" => Statement::PenUp,
+            "pendown" | "pd" => Statement::PenDown,
+            "home" => Statement::Home,
+            "width" => Statement::Width(self.expr(inputs)?),
+            "face" => {
+                let name = self.next().map(|t| t.text.to_lowercase()).unwrap_or_default();
+                let direction = Direction::ALL
+                    .into_iter()
+                    .find(|d| format!("{:?}", d).to_lowercase() == name)
+                    .ok_or_else(|| TurtleError {
+                        line,
+                        message: format!("'{}' isn't a direction; use up, down, left, right, upleft, upright, downleft or downright", name),
+                    })?;
+                Statement::Face(direction)
+            }
+            "color" | "colour" => {
+                let color = self.next().map(|t| t.text).unwrap_or_default();
+                // Only letters or a #hex code, so nothing odd can end up in the SVG file.
+                let is_name = !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic());
+                let is_hex = color.len() > 1 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+                if !is_name && !is_hex {
+                    return error(line, format!("'{}' isn't a colour; use a name like red or a code like #ff8800", color));
+                }
+                Statement::Color(color)
+            }
+            "repeat" => {
+                let times = self.expr(inputs)?;
+                match self.next() {
+                    Some(token) if token.text == "[" => {}
+                    _ => return error(line, String::from("`repeat` needs a count and then commands in [ ]")),
+                }
+                if self.nesting >= MAX_NESTING {
+                    return error(line, format!("repeats can't be more than {} deep", MAX_NESTING));
+                }
+                self.nesting += 1;
+                let body = self.statements(inputs)?;
+                self.nesting -= 1;
+                match self.next() {
+                    Some(token) if token.text == "]" => {}
+                    _ => return error(line, String::from("the [ after `repeat` is never closed")),
+                }
+                Statement::Repeat(times, body)
+            }
+            _ => match self.arity.get(&word) {
+                Some(&count) => {
+                    let mut args = Vec::new();
+                    for _ in 0..count {
+                        args.push(self.expr(inputs)?);
+                    }
+                    Statement::Call(word, args)
+                }
+                None => return error(line, format!("'{}' isn't a command or a procedure", token.text)),
+            },
+        };
+        Ok(statement)
+    }
+
+    // expr: term, then any number of + term or - term.
+    fn expr(&mut self, inputs: &[String]) -> Result<Expr, TurtleError> {
+        let mut left = self.term(inputs)?;
+        while let Some(op @ ("+" | "-")) = self.peek() {
+            let op = op.chars().next().unwrap_or('+');
+            self.next();
+            left = Expr::Binary(Box::new(left), op, Box::new(self.term(inputs)?));
+        }
+        Ok(left)
+    }
+
+    // term: factor, then any number of * factor or / factor. Doing * and / here,
+    // one level down from + and -, is what makes 2 + 3 * 4 come out as 14.
+    fn term(&mut self, inputs: &[String]) -> Result<Expr, TurtleError> {
+        let mut left = self.factor(inputs)?;
+        while let Some(op @ ("*" | "/")) = self.peek() {
+            let op = op.chars().next().unwrap_or('*');
+            self.next();
+            left = Expr::Binary(Box::new(left), op, Box::new(self.factor(inputs)?));
+        }
+        Ok(left)
+    }
+
+    fn factor(&mut self, inputs: &[String]) -> Result<Expr, TurtleError> {
+        let line = self.line();
+        let Some(token) = self.next() else {
+            return error(line, String::from("expected a number at the end of the program"));
+        };
+        match token.text.as_str() {
+            "-" => Ok(Expr::Negate(Box::new(self.factor(inputs)?))),
+            "(" => {
+                let inner = self.expr(inputs)?;
+                match self.next() {
+                    Some(close) if close.text == ")" => Ok(inner),
+                    _ => error(line, String::from("a ( is never closed")),
+                }
+            }
+            text if text.starts_with(':') => {
+                let name = text[1..].to_lowercase();
+                if inputs.contains(&name) {
+                    Ok(Expr::Input(name))
+                } else {
+                    error(line, format!("'{}' isn't an input of this procedure", text))
+                }
+            }
+            text => match text.parse::<f64>() {
+                Ok(n) if n.is_finite() => Ok(Expr::Number(n)),
+                _ => error(line, format!("expected a number, found '{}'", text)),
+            },
+        }
+    }
+}
+
+fn is_name(text: &str) -> bool {
+    text.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn command_word(word: &str) -> bool {
+    matches!(
+        word,
+        "forward" | "fd" | "back" | "bk" | "left" | "lt" | "right" | "rt" | "face" | "penup" | "pu" | "pendown" | "pd"
+            | "color" | "colour" | "width" | "home" | "repeat" | "to" | "end"
+    )
+}
+
+pub fn parse(source: &str) -> Result<Program, TurtleError> {
+    let mut parser = Parser {
+        tokens: tokenize(source),
+        position: 0,
+        arity: HashMap::new(),
+        procedures: HashMap::new(),
+        nesting: 0,
+    };
+    let statements = parser.statements(&[])?;
+    if let Some(word) = parser.peek() {
+        return error(parser.line(), format!("unexpected '{}'", word));
+    }
+    Ok(Program {
+        procedures: parser.procedures,
+        statements,
+    })
+}
+
+// One straight line drawn by the turtle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub from: (f64, f64),
+    pub to: (f64, f64),
+    pub color: String,
+    pub width: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turtle {
+    pub x: f64,
+    pub y: f64,
+    // In degrees, clockwise from up: 0 is up, 90 is right, 180 is down.
+    pub heading: f64,
+    pub pen_down: bool,
+    pub color: String,
+    pub width: f64,
+    pub lines: Vec<Line>,
+    // How many statements have run, to stop a program that never ends.
+    steps: usize,
+}
+
+impl Default for Turtle {
+    fn default() -> Self {
+        Turtle::new()
+    }
+}
+
+impl Turtle {
+    pub fn new() -> Turtle {
+        Turtle {
+            x: 0.0,
+            y: 0.0,
+            heading: 0.0,
+            pen_down: true,
+            color: String::from("black"),
+            width: 1.0,
+            lines: Vec::new(),
+            steps: 0,
+        }
+    }
+
+    // Turns to face one of the eight directions. They're 45 degrees apart, going clockwise.
+    pub fn face(&mut self, direction: Direction) {
+        let index = Direction::ALL.iter().position(|&d| d == direction).unwrap_or(0);
+        self.heading = index as f64 * 45.0;
+    }
+
+    pub fn turn(&mut self, degrees: f64) {
+        self.heading = (self.heading + degrees).rem_euclid(360.0);
+    }
+
+    pub fn forward(&mut self, distance: f64) {
+        // Screen y goes down, so moving up means y gets smaller.
+        let radians = self.heading.to_radians();
+        let x = self.x + distance * radians.sin();
+        let y = self.y - distance * radians.cos();
+        if self.pen_down && distance != 0.0 {
+            self.lines.push(Line {
+                from: (self.x, self.y),
+                to: (x, y),
+                color: self.color.clone(),
+                width: self.width,
+            });
+        }
+        self.x = x;
+        self.y = y;
+    }
+
+    // Runs a whole program.
+    pub fn run(&mut self, program: &Program) -> Result<(), TurtleError> {
+        self.run_block(program, &program.statements, &HashMap::new(), 0)
+    }
+
+    fn run_block(
+        &mut self,
+        program: &Program,
+        statements: &[(usize, Statement)],
+        inputs: &HashMap<String, f64>,
+        depth: usize,
+    ) -> Result<(), TurtleError> {
+        for (line, statement) in statements {
+            self.run_statement(program, *line, statement, inputs, depth)?;
+        }
+        Ok(())
+    }
+
+    // Counts one statement or one time round a repeat, and stops the program if there have been too many.
+    fn count_step(&mut self, line: usize) -> Result<(), TurtleError> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return error(line, format!("the program ran more than {} commands, so it was stopped", MAX_STEPS));
+        }
+        Ok(())
+    }
+
+    fn run_statement(
+        &mut self,
+        program: &Program,
+        line: usize,
+        statement: &Statement,
+        inputs: &HashMap<String, f64>,
+        depth: usize,
+    ) -> Result<(), TurtleError> {
+        self.count_step(line)?;
+        let value = |expr: &Expr| evaluate(expr, inputs).map_err(|message| TurtleError { line, message });
+
+        match statement {
+            Statement::Forward(distance) => self.forward(value(distance)?),
+            Statement::Back(distance) => self.forward(-value(distance)?),
+            Statement::Left(degrees) => self.turn(-value(degrees)?),
+            Statement::Right(degrees) => self.turn(value(degrees)?),
+            Statement::Face(direction) => self.face(*direction),
+            Statement::PenUp => self.pen_down = false,
+            Statement::PenDown => self.pen_down = true,
+            Statement::Color(color) => self.color = color.clone(),
+            Statement::Width(width) => {
+                let width = value(width)?;
+                if !width.is_finite() || width <= 0.0 {
+                    return error(line, format!("the width must be more than 0, not {}", width));
+                }
+                self.width = width;
+            }
+            Statement::Home => {
+                self.x = 0.0;
+                self.y = 0.0;
+                self.heading = 0.0;
+            }
+            Statement::Repeat(times, body) => {
+                let times = value(times)?;
+                if times < 0.0 || times.fract() != 0.0 {
+                    return error(line, format!("repeat needs a whole number that isn't negative, not {}", times));
+                }
+                for _ in 0..times as u64 {
+                    // Each time round counts as a step too, or `repeat 100000000 [ ]` would never stop.
+                    self.count_step(line)?;
+                    self.run_block(program, body, inputs, depth)?;
+                }
+            }
+            Statement::Call(name, args) => {
+                if depth >= MAX_CALL_DEPTH {
+                    return error(line, format!("procedures called each other more than {} deep", MAX_CALL_DEPTH));
+                }
+                let procedure = &program.procedures[name];
+                let mut values = HashMap::new();
+                for (input, arg) in procedure.inputs.iter().zip(args) {
+                    values.insert(input.clone(), value(arg)?);
+                }
+                self.run_block(program, &procedure.body, &values, depth + 1)?;
+            }
+        }
+        // Huge steps could add up to infinity, or make the picture too big for the SVG's numbers.
+        if !(self.x.abs() <= MAX_DISTANCE && self.y.abs() <= MAX_DISTANCE) {
+            return error(line, format!("the turtle went more than {} away from the middle", MAX_DISTANCE));
+        }
+        Ok(())
+    }
+
+    // The drawing as an SVG file: a text format for pictures that web browsers can show.
+    pub fn to_svg(&self) -> String {
+        // The smallest box around every line, with some space around the edge.
+        let margin = 10.0;
+        let points = self.lines.iter().flat_map(|l| [l.from, l.to]);
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (0.0_f64, 0.0_f64, 0.0_f64, 0.0_f64);
+        for (x, y) in points {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        let (left, top) = (min_x - margin, min_y - margin);
+        let (width, height) = (max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+            number(left),
+            number(top),
+            number(width),
+            number(height),
+            number(width),
+            number(height)
+        );
+        svg.push_str(&format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+            number(left),
+            number(top),
+            number(width),
+            number(height)
+        ));
+        for line in &self.lines {
+            svg.push_str(&format!(
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\"/>\n",
+                number(line.from.0),
+                number(line.from.1),
+                number(line.to.0),
+                number(line.to.1),
+                line.color,
+                number(line.width)
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save_svg(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_svg()).map_err(|e| format!("Could not write {}: {}", path, e))
+    }
+}
+
+fn evaluate(expr: &Expr, inputs: &HashMap<String, f64>) -> Result<f64, String> {
+    match expr {
+        Expr::Number(n) => Ok(*n),
+        Expr::Input(name) => inputs
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!(":{} has no value here", name)),
+        Expr::Negate(inner) => Ok(-evaluate(inner, inputs)?),
+        Expr::Binary(left, op, right) => {
+            let (a, b) = (evaluate(left, inputs)?, evaluate(right, inputs)?);
+            let answer = match op {
+                '+' => a + b,
+                '-' => a - b,
+                '*' => a * b,
+                _ if b == 0.0 => return Err(String::from("can't divide by 0")),
+                _ => a / b,
+            };
+            // A number too big for an f64 becomes infinity, which would end up in the SVG as "inf".
+            if !answer.is_finite() {
+                return Err(String::from("a number got too big to work with"));
+            }
+            Ok(answer)
+        }
+    }
+}
+
+// Rounds to 2 decimal places and drops any zeros at the end, so 10.50 is 10.5 and 3.00 is 3.
+fn number(n: f64) -> String {
+    let text = format!("{:.2}", n);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { String::from("0") } else { text.to_string() }
+}
+
+// Parses and runs a program, returning the turtle with its drawing.
+pub fn draw(source: &str) -> Result<Turtle, TurtleError> {
+    let program = parse(source)?;
+    let mut turtle = Turtle::new();
+    turtle.run(&program)?;
+    Ok(turtle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_svg() {
+        let turtle = draw("repeat 4 [ fd 50 rt 90 ]").unwrap();
+        let expected = "\
+<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-10 -60 70 70\" width=\"70\" height=\"70\">
+  <rect x=\"-10\" y=\"-60\" width=\"70\" height=\"70\" fill=\"white\"/>
+  <line x1=\"0\" y1=\"0\" x2=\"0\" y2=\"-50\" stroke=\"black\" stroke-width=\"1\" stroke-linecap=\"round\"/>
+  <line x1=\"0\" y1=\"-50\" x2=\"50\" y2=\"-50\" stroke=\"black\" stroke-width=\"1\" stroke-linecap=\"round\"/>
+  <line x1=\"50\" y1=\"-50\" x2=\"50\" y2=\"0\" stroke=\"black\" stroke-width=\"1\" stroke-linecap=\"round\"/>
+  <line x1=\"50\" y1=\"0\" x2=\"0\" y2=\"0\" stroke=\"black\" stroke-width=\"1\" stroke-linecap=\"round\"/>
+</svg>
+";
+        assert_eq!(turtle.to_svg(), expected);
+    }
+
+    #[test]
+    fn procedure_draws_the_same_square() {
+        let program = "to square :size\n  repeat 4 [ fd :size rt 90 ]\nend\nsquare 25 * 2";
+        assert_eq!(draw(program).unwrap().lines, draw("repeat 4 [ fd 50 rt 90 ]").unwrap().lines);
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let error = draw("fd 10\nwobble 3").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.message, "'wobble' isn't a command or a procedure");
+        assert_eq!(draw("fd 10 / 0").unwrap_err().message, "can't divide by 0");
+    }
+
+    #[test]
+    fn runaway_programs_are_stopped() {
+        assert!(draw("repeat 100000000000000 [ ]").unwrap_err().message.contains("more than 100000 commands"));
+        assert!(draw("to spin\n  spin\nend\nspin").is_err());
+        let nested = format!("{}fd 1{}", "repeat 2 [ ".repeat(MAX_NESTING + 1), " ]".repeat(MAX_NESTING + 1));
+        assert!(draw(&nested).unwrap_err().message.contains("deep"));
+    }
+
+    #[test]
+    fn numbers_must_stay_finite() {
+        assert_eq!(draw("fd 1e308 * 10").unwrap_err().message, "a number got too big to work with");
+        assert!(draw("width 1e308 * 10").is_err());
+        assert!(draw("fd 1e308 home bk 1e308").is_err());
+    }
+}
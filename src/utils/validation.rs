@@ -0,0 +1,416 @@
+// Checking that a struct's fields make sense, and reporting every problem at once.
+//
+// Rust checks types for us: a Person's age is always a u32. But it can't know
+// that a name shouldn't be empty or that nobody is 300 years old, so
+// `Person { name: String::new(), age: 300 }` is accepted. A type that
+// implements Validate can be asked whether its values make sense.
+//
+// Each type lists rules for its fields with a Validator:
+//
+//     let mut v = Validator::new();
+//     v.text("name", &self.name).not_empty().length(1..=100);
+//     v.number("age", self.age).range(0..=150);
+//     v.finish()
+//
+// Unlike a function that returns at the first `?`, the Validator keeps going
+// and collects every Violation, so a form with three mistakes shows all three.
+// Each violation has the path of the field it's about, like "name" or
+// "assignments[1].weight" for a field of a struct inside a list.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    // Which field, like "age" or "assignments[0].name".
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+pub trait Validate {
+    // Ok if every rule passes, or every violation found.
+    fn validate(&self) -> Result<(), Vec<Violation>>;
+}
+
+// Joins violations into one line, for places that want a single error String.
+pub fn describe(violations: &[Violation]) -> String {
+    violations.iter().map(|v| v.to_string()).collect::<Vec<String>>().join("; ")
+}
+
+#[derive(Debug, Default)]
+pub struct Validator {
+    violations: Vec<Violation>,
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    // Starts the rules for a text field.
+    pub fn text<'a>(&'a mut self, field: &str, value: &'a str) -> TextRules<'a> {
+        TextRules {
+            violations: &mut self.violations,
+            path: field.to_string(),
+            value,
+            failed: false,
+        }
+    }
+
+    // Starts the rules for a number field. Any number type works: u32, f64, ...
+    pub fn number<N>(&mut self, field: &str, value: N) -> NumberRules<'_, N>
+    where
+        N: PartialOrd + Copy + fmt::Display,
+    {
+        NumberRules {
+            violations: &mut self.violations,
+            path: field.to_string(),
+            value,
+            failed: false,
+        }
+    }
+
+    // A rule that doesn't fit the others, like "the end date is after the
+    // start date". The violation is recorded if `ok` is false.
+    pub fn check(&mut self, field: &str, ok: bool, message: &str) {
+        if !ok {
+            self.add(field, message);
+        }
+    }
+
+    pub fn add(&mut self, field: &str, message: &str) {
+        self.violations.push(Violation {
+            path: field.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    // Validates a struct inside this one. Its violations get this field's
+    // name in front, so "weight" becomes "assignment.weight".
+    pub fn nested<T: Validate>(&mut self, field: &str, value: &T) {
+        if let Err(violations) = value.validate() {
+            for v in violations {
+                self.violations.push(Violation {
+                    path: format!("{}.{}", field, v.path),
+                    message: v.message,
+                });
+            }
+        }
+    }
+
+    // Validates every item in a list. Paths get the index: "assignments[2].name".
+    pub fn each<T: Validate>(&mut self, field: &str, items: &[T]) {
+        for (i, item) in items.iter().enumerate() {
+            self.nested(&format!("{}[{}]", field, i), item);
+        }
+    }
+
+    pub fn finish(self) -> Result<(), Vec<Violation>> {
+        if self.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(self.violations)
+        }
+    }
+}
+
+// The rules for one text field. Each method returns the TextRules again, so
+// they can be chained. Once one rule fails, the rest are skipped: an empty
+// name is also too short, but saying so twice doesn't help anyone.
+pub struct TextRules<'a> {
+    violations: &'a mut Vec<Violation>,
+    path: String,
+    value: &'a str,
+    failed: bool,
+}
+
+impl TextRules<'_> {
+    fn rule(mut self, ok: impl FnOnce(&str) -> Result<(), String>) -> Self {
+        if !self.failed
+            && let Err(message) = ok(self.value)
+        {
+            self.violations.push(Violation {
+                path: self.path.clone(),
+                message,
+            });
+            self.failed = true;
+        }
+        self
+    }
+
+    // Spaces alone count as empty.
+    pub fn not_empty(self) -> Self {
+        self.rule(|value| {
+            if value.trim().is_empty() {
+                return Err(String::from("can't be empty"));
+            }
+            Ok(())
+        })
+    }
+
+    // The length in characters (not bytes, so "José" is 4).
+    pub fn length(self, allowed: RangeInclusive<usize>) -> Self {
+        self.rule(|value| {
+            let length = value.chars().count();
+            if !allowed.contains(&length) {
+                return Err(format!(
+                    "must be {} to {} characters long, not {}",
+                    allowed.start(),
+                    allowed.end(),
+                    length
+                ));
+            }
+            Ok(())
+        })
+    }
+
+    // The whole value must match the pattern. See matches() below.
+    pub fn pattern(self, pattern: &str) -> Self {
+        self.rule(|value| match matches(pattern, value) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(format!("must match the pattern {}", pattern)),
+            Err(e) => Err(format!("the pattern {} is broken: {}", pattern, e)),
+        })
+    }
+
+    // Any other rule, as a closure that returns the message when the value is wrong.
+    pub fn custom<F: FnOnce(&str) -> Result<(), String>>(self, rule: F) -> Self {
+        self.rule(rule)
+    }
+}
+
+// The rules for one number field. Like TextRules, they stop at the first failure.
+pub struct NumberRules<'a, N> {
+    violations: &'a mut Vec<Violation>,
+    path: String,
+    value: N,
+    failed: bool,
+}
+
+impl<N: PartialOrd + Copy + fmt::Display> NumberRules<'_, N> {
+    fn rule(mut self, ok: impl FnOnce(N) -> Result<(), String>) -> Self {
+        if !self.failed
+            && let Err(message) = ok(self.value)
+        {
+            self.violations.push(Violation {
+                path: self.path.clone(),
+                message,
+            });
+            self.failed = true;
+        }
+        self
+    }
+
+    // From the start to the end, both included: range(0..=150).
+    // NaN is in no range, so a NaN score fails this too.
+    pub fn range(self, allowed: RangeInclusive<N>) -> Self {
+        self.rule(|value| {
+            if !allowed.contains(&value) {
+                return Err(format!("must be from {} to {}, not {}", allowed.start(), allowed.end(), value));
+            }
+            Ok(())
+        })
+    }
+
+    // Strictly more than `limit`, for things like weights that can't be 0.
+    pub fn above(self, limit: N) -> Self {
+        self.rule(|value| {
+            // Compared this way rather than with <= so NaN fails: NaN isn't
+            // greater, less or equal to anything.
+            if value.partial_cmp(&limit) != Some(Ordering::Greater) {
+                return Err(format!("must be above {}, not {}", limit, value));
+            }
+            Ok(())
+        })
+    }
+
+    pub fn custom<F: FnOnce(N) -> Result<(), String>>(self, rule: F) -> Self {
+        self.rule(rule)
+    }
+}
+
+// A small pattern language, like a cut-down regular expression:
+//   a       the letter a (any character that isn't special matches itself)
+//   .       any one character
+//   [abc]   one of a, b or c;  [a-z] a range;  [^0-9] anything but a digit
+//   \d \w \s  a digit, a letter/digit/underscore, a space or tab
+//   \.      a real dot (a backslash makes any special character ordinary)
+// and after any of those, how many times it can repeat:
+//   *  0 or more,  +  1 or more,  ?  0 or 1,  {3}  exactly 3,  {2,5}  2 to 5,  {2,}  2 or more
+// The pattern has to match the whole text, not just part of it.
+pub fn matches(pattern: &str, text: &str) -> Result<bool, String> {
+    let pieces = parse_pattern(pattern)?;
+    let text: Vec<char> = text.chars().collect();
+    Ok(match_from(&pieces, &text))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Atom {
+    Any,
+    Char(char),
+    // (ranges, negated). A single character c is the range c..=c.
+    Class(Vec<(char, char)>, bool),
+}
+
+impl Atom {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Atom::Any => true,
+            Atom::Char(expected) => c == *expected,
+            Atom::Class(ranges, negated) => ranges.iter().any(|(low, high)| (*low..=*high).contains(&c)) != *negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Piece {
+    atom: Atom,
+    min: usize,
+    // None means no limit.
+    max: Option<usize>,
+}
+
+fn escape_class(c: char) -> Option<Atom> {
+    match c {
+        'd' => Some(Atom::Class(vec![('0', '9')], false)),
+        'w' => Some(Atom::Class(vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')], false)),
+        's' => Some(Atom::Class(vec![(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')], false)),
+        _ => None,
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Result<Vec<Piece>, String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut pieces = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let atom = match chars[i] {
+            '.' => Atom::Any,
+            '\\' => {
+                i += 1;
+                let c = *chars.get(i).ok_or("it ends with a \\")?;
+                escape_class(c).unwrap_or(Atom::Char(c))
+            }
+            '[' => {
+                let (atom, end) = parse_class(&chars, i)?;
+                i = end;
+                atom
+            }
+            c @ ('*' | '+' | '?' | '{') => return Err(format!("'{}' has nothing before it to repeat", c)),
+            ']' | '}' => return Err(format!("'{}' doesn't close anything", chars[i])),
+            c => Atom::Char(c),
+        };
+        i += 1;
+
+        let (min, max) = match chars.get(i) {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let close = chars[i..].iter().position(|&c| c == '}').ok_or("a { is never closed")? + i;
+                let inside: String = chars[i + 1..close].iter().collect();
+                i = close;
+                parse_count(&inside)?
+            }
+            _ => {
+                pieces.push(Piece { atom, min: 1, max: Some(1) });
+                continue;
+            }
+        };
+        i += 1;
+        pieces.push(Piece { atom, min, max });
+    }
+    Ok(pieces)
+}
+
+// Reads a [...] class starting at chars[start]. Returns it and where the ] is.
+fn parse_class(chars: &[char], start: usize) -> Result<(Atom, usize), String> {
+    let mut i = start + 1;
+    let negated = chars.get(i) == Some(&'^');
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    // A ] straight after [ or [^ is an ordinary character, so []] works.
+    let mut first = true;
+    loop {
+        let c = *chars.get(i).ok_or("a [ is never closed")?;
+        if c == ']' && !first {
+            break;
+        }
+        first = false;
+        let low = if c == '\\' {
+            i += 1;
+            let escaped = *chars.get(i).ok_or("it ends with a \\")?;
+            if let Some(Atom::Class(more, _)) = escape_class(escaped) {
+                ranges.extend(more);
+                i += 1;
+                continue;
+            }
+            escaped
+        } else {
+            c
+        };
+        // a-z is a range, but a - at the end (like [a-]) is just a dash.
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|&c| c != ']') {
+            let high = chars[i + 2];
+            if high < low {
+                return Err(format!("the range {}-{} is backwards", low, high));
+            }
+            ranges.push((low, high));
+            i += 3;
+        } else {
+            ranges.push((low, low));
+            i += 1;
+        }
+    }
+    Ok((Atom::Class(ranges, negated), i))
+}
+
+// "3" => exactly 3, "2,5" => 2 to 5, "2," => 2 or more.
+fn parse_count(inside: &str) -> Result<(usize, Option<usize>), String> {
+    let number = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("{{{}}} isn't a repeat count", inside))
+    };
+    match inside.split_once(',') {
+        None => {
+            let n = number(inside)?;
+            Ok((n, Some(n)))
+        }
+        Some((low, high)) if high.trim().is_empty() => Ok((number(low)?, None)),
+        Some((low, high)) => {
+            let (low, high) = (number(low)?, number(high)?);
+            if high < low {
+                return Err(format!("{{{}}} is backwards", inside));
+            }
+            Ok((low, Some(high)))
+        }
+    }
+}
+
+// Tries to match the pieces against the whole text. A repeated piece first
+// takes as many characters as it can, then gives them back one at a time
+// until the rest of the pattern matches ("backtracking"), so "a*ab" matches "aaab".
+fn match_from(pieces: &[Piece], text: &[char]) -> bool {
+    let Some((piece, rest)) = pieces.split_first() else {
+        return text.is_empty();
+    };
+    let most = text
+        .iter()
+        .take(piece.max.unwrap_or(usize::MAX))
+        .take_while(|&&c| piece.atom.matches(c))
+        .count();
+    if most < piece.min {
+        return false;
+    }
+    (piece.min..=most).rev().any(|taken| match_from(rest, &text[taken..]))
+}